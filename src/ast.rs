@@ -1,5 +1,35 @@
+use crate::token::Span;
+use num_bigint::BigInt;
 use std::fmt::{self, Formatter};
 
+/// An AST node paired with the span of source it was parsed from.
+///
+/// Spans are ignored when comparing nodes, so two trees are equal when they
+/// have the same shape regardless of where they came from.
+#[derive(Clone, Debug)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+}
+
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Spanned::new(node, Span::default())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct Ident(pub String);
 
@@ -8,6 +38,7 @@ pub enum Prefix {
     Plus,
     Minus,
     Not,
+    BitNot,
 }
 
 impl fmt::Display for Prefix {
@@ -16,6 +47,7 @@ impl fmt::Display for Prefix {
             Prefix::Plus => write!(f, "+"),
             Prefix::Minus => write!(f, "-"),
             Prefix::Not => write!(f, "!"),
+            Prefix::BitNot => write!(f, "~"),
         }
     }
 }
@@ -33,6 +65,18 @@ pub enum Infix {
     GreaterThan,
     LessThanEqual,
     LessThan,
+    And,
+    Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Power,
+    /// `x |> f(a)`, which calls `f(x, a)`.
+    Pipe,
+    /// `a ?? b`, which is `b` only when `a` is null.
+    Coalesce,
 }
 
 impl fmt::Display for Infix {
@@ -49,6 +93,16 @@ impl fmt::Display for Infix {
             Infix::GreaterThan => write!(f, ">"),
             Infix::LessThanEqual => write!(f, "<="),
             Infix::LessThan => write!(f, "<"),
+            Infix::And => write!(f, "&&"),
+            Infix::Or => write!(f, "||"),
+            Infix::BitAnd => write!(f, "&"),
+            Infix::BitOr => write!(f, "|"),
+            Infix::BitXor => write!(f, "^"),
+            Infix::ShiftLeft => write!(f, "<<"),
+            Infix::ShiftRight => write!(f, ">>"),
+            Infix::Power => write!(f, "**"),
+            Infix::Pipe => write!(f, "|>"),
+            Infix::Coalesce => write!(f, "??"),
         }
     }
 }
@@ -56,67 +110,208 @@ impl fmt::Display for Infix {
 #[derive(PartialEq, Clone, Debug)]
 pub enum Literal {
     String(String),
-    Int(i32),
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
     Bool(bool),
-    Array(Vec<Expression>),
-    Hash(Vec<(Expression, Expression)>),
+    Null,
+    Array(Vec<Spanned<Expression>>),
+    Hash(Vec<(Spanned<Expression>, Spanned<Expression>)>),
 }
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum Expression {
     Ident(Ident),
     Literal(Literal),
-    Prefix(Prefix, Box<Expression>),
-    Infix(Infix, Box<Expression>, Box<Expression>),
+    Interpolated(Vec<Spanned<Expression>>),
+    Prefix(Prefix, Box<Spanned<Expression>>),
+    Infix(Infix, Box<Spanned<Expression>>, Box<Spanned<Expression>>),
     If {
-        condition: Box<Expression>,
+        condition: Box<Spanned<Expression>>,
         consequence: BlockStatement,
         alternative: Option<BlockStatement>,
     },
+    /// `fn(x, y = 10, ...rest) { ... }`, where `rest` collects any
+    /// positional arguments left over after `params`.
     Fn {
-        params: Vec<Ident>,
+        params: Vec<Param>,
+        rest: Option<Ident>,
         body: BlockStatement,
     },
+    /// `f(a, b, name: c)`. Keyword arguments always follow the positional
+    /// ones. An optional call, `f?.(a)`, gives null when `f` is null.
     Call {
-        function: Box<Expression>,
-        args: Vec<Expression>,
+        function: Box<Spanned<Expression>>,
+        args: Vec<Spanned<Expression>>,
+        kwargs: Vec<(Ident, Spanned<Expression>)>,
+        optional: bool,
     },
+    /// `left[index]`, or `left?[index]`, which gives null when `left` is
+    /// null.
     Index {
-        left: Box<Expression>,
-        index: Box<Expression>,
+        left: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
+        optional: bool,
+    },
+    /// `left[start:end:step]`, where any of the three may be left out.
+    Slice {
+        left: Box<Spanned<Expression>>,
+        start: Option<Box<Spanned<Expression>>>,
+        end: Option<Box<Spanned<Expression>>>,
+        step: Option<Box<Spanned<Expression>>>,
+        optional: bool,
     },
+    /// `start..end` or `start..=end`, optionally followed by `step n`.
+    Range {
+        start: Box<Spanned<Expression>>,
+        end: Box<Spanned<Expression>>,
+        inclusive: bool,
+        step: Option<Box<Spanned<Expression>>>,
+    },
+    /// Evaluates the body of the first arm whose pattern matches `subject`.
+    Match {
+        subject: Box<Spanned<Expression>>,
+        arms: Vec<Spanned<MatchArm>>,
+    },
+}
+
+/// A function parameter, with the value it takes when no argument is given
+/// for it.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Param {
+    pub name: Ident,
+    pub default: Option<Spanned<Expression>>,
+}
+
+/// A `pattern if guard => body` arm of a `match`. An arm written as a single
+/// expression has it as the only statement of its body.
+#[derive(PartialEq, Clone, Debug)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Spanned<Expression>>,
+    pub body: BlockStatement,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Pattern {
+    /// `_`, which matches anything.
+    Wildcard,
+    /// Matches anything, binding it to the name.
+    Ident(Ident),
+    /// Matches a value equal to a string, number or boolean literal.
+    Literal(Spanned<Literal>),
+    /// `[first, ...rest]`. Without a rest pattern the array must have exactly
+    /// as many items as there are patterns, otherwise the rest pattern is
+    /// matched against an array of the remaining items.
+    Array {
+        items: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    /// `{"key": pattern}`, matching a hash that has at least these keys. The
+    /// shorthand `{key}` stands for `{"key": key}`.
+    Hash(Vec<(Spanned<Literal>, Pattern)>),
 }
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum Statement {
-    Let(Ident, Expression),
-    Update(Ident, Expression),
-    Return(Expression),
-    Expression(Expression),
+    /// Binds the names in a pattern, which is usually just an identifier but
+    /// may also pull items out of an array or hash.
+    Let(Pattern, Spanned<Expression>),
+    /// Assigns to an existing variable or to an element inside one, such as
+    /// `grid[y][x]`. A compound update like `+=` carries its operator.
+    Update {
+        target: Spanned<Expression>,
+        operator: Option<Infix>,
+        value: Spanned<Expression>,
+    },
+    /// `fn name(params) { ... }`, which binds a named function. Declarations
+    /// are hoisted to the top of their block, so they can be called before
+    /// the line that declares them.
+    Fn {
+        name: Ident,
+        params: Vec<Param>,
+        rest: Option<Ident>,
+        body: BlockStatement,
+    },
+    Return(Spanned<Expression>),
+    Expression(Spanned<Expression>),
     Import(Ident),
+    While {
+        condition: Spanned<Expression>,
+        body: BlockStatement,
+    },
+    /// `for (x in iterable) { ... }`, binding `x` to each item in turn.
+    For {
+        ident: Ident,
+        iterable: Spanned<Expression>,
+        body: BlockStatement,
+    },
+    Break,
+    Continue,
 }
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct Program {
-    pub statements: Vec<Statement>,
-}
-
-impl fmt::Display for Expression {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
-    }
+    pub statements: Vec<Spanned<Statement>>,
 }
 
 #[derive(PartialEq, PartialOrd, Debug, Clone)]
 pub enum Precedence {
     Lowest,
+    Pipe,
+    Coalesce,
+    LogicalOr,
+    LogicalAnd,
     Equals,
     LessGreater,
+    Range,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     Sum,
     Product,
     Prefix,
+    Power,
     Call,
     Index,
 }
 
-pub type BlockStatement = Vec<Statement>;
+pub type BlockStatement = Vec<Spanned<Statement>>;
+
+impl Infix {
+    pub fn precedence(&self) -> Precedence {
+        match *self {
+            Infix::Pipe => Precedence::Pipe,
+            Infix::Coalesce => Precedence::Coalesce,
+            Infix::Or => Precedence::LogicalOr,
+            Infix::And => Precedence::LogicalAnd,
+            Infix::Equal | Infix::NotEqual => Precedence::Equals,
+            Infix::LessThan
+            | Infix::LessThanEqual
+            | Infix::GreaterThan
+            | Infix::GreaterThanEqual => Precedence::LessGreater,
+            Infix::BitOr => Precedence::BitOr,
+            Infix::BitXor => Precedence::BitXor,
+            Infix::BitAnd => Precedence::BitAnd,
+            Infix::ShiftLeft | Infix::ShiftRight => Precedence::Shift,
+            Infix::Plus | Infix::Minus => Precedence::Sum,
+            Infix::Multiply | Infix::Divide | Infix::Modulus => Precedence::Product,
+            Infix::Power => Precedence::Power,
+        }
+    }
+}
+
+impl Expression {
+    /// How tightly the expression binds when printed. Anything that is not an
+    /// operator is treated as an atom and never needs parentheses.
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            Expression::Prefix(..) => Precedence::Prefix,
+            Expression::Infix(infix, ..) => infix.precedence(),
+            Expression::Range { .. } => Precedence::Range,
+            Expression::Call { .. } => Precedence::Call,
+            _ => Precedence::Index,
+        }
+    }
+}
@@ -7,6 +7,7 @@ pub fn new_builtins() -> HashMap<String, Object> {
     builtins.insert(String::from("len"), Object::Builtin(monkey_len));
     builtins.insert(String::from("tail"), Object::Builtin(monkey_tail));
     builtins.insert(String::from("push"), Object::Builtin(monkey_push));
+    builtins.insert(String::from("to_array"), Object::Builtin(monkey_to_array));
     builtins
 }
 
@@ -29,8 +30,9 @@ fn monkey_len(args: Vec<Object>) -> Object {
         ));
     }
     match &args[0] {
-        Object::String(s) => Object::Int(s.len() as i32),
-        Object::Array(a) => Object::Int(a.len() as i32),
+        Object::String(s) => Object::Int(s.chars().count() as i64),
+        Object::Array(a) => Object::Int(a.len() as i64),
+        Object::Range(r) => Object::Int(r.len()),
         o => Object::Error(format!("argument to `len` not supported, got: {}", o)),
     }
 }
@@ -45,6 +47,11 @@ fn monkey_tail(args: Vec<Object>) -> Object {
 
     match &args[0] {
         Object::Array(a) => Object::Array(a[1..].to_vec()),
+        Object::Range(r) if r.len() <= 1 => Object::Range(Range { start: r.end, ..*r }),
+        Object::Range(r) => Object::Range(Range {
+            start: r.start + r.step,
+            ..*r
+        }),
         o => Object::Error(format!("argument to `tail` not supported, got: {}", o)),
     }
 }
@@ -64,6 +71,26 @@ fn monkey_push(args: Vec<Object>) -> Object {
             array.push(args[1].clone());
             Object::Array(array)
         }
+        Object::Range(r) => {
+            let mut array: Vec<Object> = r.iter().map(Object::Int).collect();
+            array.push(args[1].clone());
+            Object::Array(array)
+        }
         o => Object::Error(format!("argument to `push` not supported, got: {}", o)),
     }
 }
+
+fn monkey_to_array(args: Vec<Object>) -> Object {
+    if args.len() != 1 {
+        return Object::Error(format!(
+            "expected arguments: 1\ngiven arguments: {}",
+            args.len()
+        ));
+    }
+
+    match &args[0] {
+        Object::Array(a) => Object::Array(a.clone()),
+        Object::Range(r) => Object::Array(r.iter().map(Object::Int).collect()),
+        o => Object::Error(format!("argument to `to_array` not supported, got: {}", o)),
+    }
+}
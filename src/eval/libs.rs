@@ -3,9 +3,9 @@ use crate::{lexer::Lexer, parser::Parser};
 use std::{cell::RefCell, collections::HashMap, fs, rc::Rc};
 
 pub fn load_lib(lib: String) -> Option<HashMap<String, Object>> {
-    let libs = vec!["std"];
+    let libs = ["std"];
     let mut methods = HashMap::new();
-    methods.insert("std", vec!["map", "first", "last", "while", "range", "abs"]);
+    methods.insert("std", vec!["map", "first", "last", "range", "abs"]);
 
     if !libs.contains(&lib.as_str()) {
         return None;
@@ -22,7 +22,7 @@ pub fn load_lib(lib: String) -> Option<HashMap<String, Object>> {
     let mut eval = Eval::new(Rc::new(RefCell::new(Env::new())));
     eval.eval(program);
 
-    let store = (&*eval.env.borrow()).to_owned().store;
+    let store = eval.env.borrow().to_owned().store;
     let mut final_env = HashMap::new();
     match methods.get(lib.as_str()) {
         Some(m) => m
@@ -6,14 +6,20 @@ pub mod object;
 #[cfg(test)]
 pub mod test;
 
-use crate::ast::*;
+use crate::{ast::*, token::Span};
 use builtins::new_builtins;
 use env::Env;
-use object::Object;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use object::{Function, Object, Range};
+use std::{cell::RefCell, collections::HashMap, ops::ControlFlow, rc::Rc};
 
 use self::libs::load_lib;
 
+/// The most bits the result of a `<<` or `**` may have before it is reported
+/// as an overflow instead of being computed.
+const MAX_INT_BITS: u64 = 1 << 20;
+
 pub struct Eval {
     pub env: Rc<RefCell<Env>>,
 }
@@ -31,10 +37,30 @@ impl Eval {
         matches!(object, Object::Error(_))
     }
 
+    /// Whether `object` ends the evaluation of whatever produced it: an error,
+    /// or a `return`, `break` or `continue` reached inside a nested block.
+    fn is_abrupt(&mut self, object: &Object) -> bool {
+        matches!(
+            object,
+            Object::Error(_) | Object::Return(_) | Object::Break | Object::Continue
+        )
+    }
+
+    /// Appends the location of `span` to an error raised while evaluating it.
+    /// Only errors that originate at `span` should pass through here, errors
+    /// bubbling up from sub-expressions already carry their own location.
+    fn error_at(&mut self, object: Object, span: Span) -> Object {
+        match object {
+            Object::Error(msg) => Object::Error(format!("{} at {}", msg, span)),
+            o => o,
+        }
+    }
+
     pub fn eval(&mut self, program: Program) -> Option<Object> {
         let mut result = None;
+        self.hoist_functions(&program.statements);
 
-        for statement in program.statements {
+        for statement in &program.statements {
             match self.eval_statement(statement) {
                 Some(Object::Error(val)) => return Some(Object::Error(val)),
                 Some(Object::Return(val)) => return Some(*val),
@@ -45,63 +71,200 @@ impl Eval {
         result
     }
 
-    fn eval_statement(&mut self, statement: Statement) -> Option<Object> {
-        match statement {
+    fn eval_statement(&mut self, statement: &Spanned<Statement>) -> Option<Object> {
+        let span = statement.span;
+        match &statement.node {
             Statement::Expression(e) => self.eval_expr(e),
+            // Already bound by `hoist_functions` when the block started.
+            Statement::Fn { .. } => None,
             Statement::Return(e) => {
-                let val = match self.eval_expr(e) {
-                    Some(v) => v,
-                    None => return None,
-                };
+                let val = self.eval_expr(e)?;
 
                 Some(Object::Return(Box::new(val)))
             }
-            Statement::Let(i, v) => {
-                let val = match self.eval_expr(v) {
-                    Some(value) => value,
-                    None => return None,
-                };
-                if self.is_error(&val) {
-                    Some(val)
-                } else {
-                    let Ident(name) = i;
-                    self.env.borrow_mut().set(name, val);
-                    None
+            Statement::Let(pattern, v) => {
+                let val = self.eval_expr(v)?;
+                if self.is_abrupt(&val) {
+                    return Some(val);
+                }
+                let mut bindings = vec![];
+                if let Err(msg) = self.match_pattern(pattern, &val, &mut bindings) {
+                    return Some(self.error_at(Object::Error(msg), span));
+                }
+                for (name, value) in bindings {
+                    self.env.borrow_mut().set(name, value);
                 }
+                None
             }
-            Statement::Update(i, v) => {
-                let Ident(name) = i;
-                let val = match self.eval_expr(v) {
-                    Some(value) => value,
-                    None => return None,
-                };
-                if self.is_error(&val) {
-                    Some(val)
-                } else {
-                    let mut env = self.env.borrow_mut();
-                    match env.get(&name) {
-                        Some(_) => {
-                            env.update(name, val);
-                            None
+            Statement::Update {
+                target,
+                operator,
+                value,
+            } => {
+                let val = self.eval_expr(value)?;
+                if self.is_abrupt(&val) {
+                    return Some(val);
+                }
+                self.eval_update(target, operator.clone(), val)
+            }
+            Statement::While { condition, body } => self.eval_while_statement(condition, body),
+            Statement::For {
+                ident,
+                iterable,
+                body,
+            } => self.eval_for_statement(ident, iterable, body),
+            Statement::Break => Some(Object::Break),
+            Statement::Continue => Some(Object::Continue),
+            Statement::Import(Ident(lib)) => self
+                .extend_global_env(lib.clone())
+                .map(|err| self.error_at(err, span)),
+        }
+    }
+
+    /// Binds every `fn name(...)` declaration among `statements` before any
+    /// of them run, so that functions can be called ahead of their
+    /// declaration and can refer to each other in any order.
+    fn hoist_functions(&mut self, statements: &[Spanned<Statement>]) {
+        for statement in statements {
+            if let Statement::Fn {
+                name,
+                params,
+                rest,
+                body,
+            } = &statement.node
+            {
+                let function = Object::Fn(Rc::new(Function {
+                    name: Some(name.clone()),
+                    params: params.clone(),
+                    rest: rest.clone(),
+                    body: body.clone(),
+                    env: self.env.clone(),
+                }));
+                self.env.borrow_mut().set(name.0.clone(), function);
+            }
+        }
+    }
+
+    /// Assigns `value` to `target`, first combining it with the current value
+    /// for a compound update. Indexed targets like `grid[y][x]` rebuild the
+    /// containers along the path and store the result in the variable at its
+    /// root, through `Env::update` so that closures see the change.
+    fn eval_update(
+        &mut self,
+        target: &Spanned<Expression>,
+        operator: Option<Infix>,
+        value: Object,
+    ) -> Option<Object> {
+        let span = target.span;
+        let mut indices = vec![];
+        let mut root = target;
+        let name = loop {
+            match &root.node {
+                Expression::Index { left, index, .. } => {
+                    indices.push(index);
+                    root = left;
+                }
+                Expression::Ident(Ident(name)) => break name.clone(),
+                _ => {
+                    let err = Object::Error(String::from("invalid update target"));
+                    return Some(self.error_at(err, span));
+                }
+            }
+        };
+
+        let mut keys = vec![];
+        for index in indices.into_iter().rev() {
+            let key = self.eval_expr(index)?;
+            if self.is_abrupt(&key) {
+                return Some(key);
+            }
+            keys.push(key);
+        }
+
+        let current = self.env.borrow_mut().get(&name);
+        let current = match current {
+            Some(obj) => obj,
+            None => {
+                let err = Object::Error(format!("identifier not found: {}", name));
+                return Some(self.error_at(err, span));
+            }
+        };
+        match self.eval_assignment(current, &keys, operator, value) {
+            Ok(obj) => {
+                self.env.borrow_mut().update(name, obj);
+                None
+            }
+            Err(err) => Some(self.error_at(err, span)),
+        }
+    }
+
+    /// Returns `current` with the element at the path `keys` replaced by
+    /// `value`, or `value` itself once the path is empty.
+    fn eval_assignment(
+        &mut self,
+        current: Object,
+        keys: &[Object],
+        operator: Option<Infix>,
+        value: Object,
+    ) -> Result<Object, Object> {
+        let (key, rest) = match keys.split_first() {
+            Some(split) => split,
+            None => {
+                return match operator {
+                    Some(infix) => {
+                        let obj = self.eval_infix_expr(infix, current, value);
+                        if self.is_error(&obj) {
+                            Err(obj)
+                        } else {
+                            Ok(obj)
                         }
-                        None => Some(Object::Error(format!("identifier not found: {}", name))),
                     }
+                    None => Ok(value),
+                }
+            }
+        };
+
+        match (current, key) {
+            (Object::Array(mut arr), Object::Int(i)) => {
+                let index = if *i < 0 { arr.len() as i64 + i } else { *i };
+                if index < 0 || index >= arr.len() as i64 {
+                    return Err(Object::Error(format!("index out of bounds: {}", i)));
                 }
+                let element = std::mem::replace(&mut arr[index as usize], Object::Null);
+                arr[index as usize] = self.eval_assignment(element, rest, operator, value)?;
+                Ok(Object::Array(arr))
             }
-            Statement::Import(i) => {
-                let Ident(lib) = i;
-                self.extend_global_env(lib)
+            (
+                Object::Hash(mut hash),
+                key @ (Object::Int(_) | Object::BigInt(_) | Object::Bool(_) | Object::String(_)),
+            ) => {
+                let element = match hash.remove(key) {
+                    Some(obj) => obj,
+                    None if rest.is_empty() && operator.is_none() => Object::Null,
+                    None => return Err(Object::Error(format!("key not found: {}", key))),
+                };
+                let element = self.eval_assignment(element, rest, operator, value)?;
+                hash.insert(key.clone(), element);
+                Ok(Object::Hash(hash))
             }
+            (Object::Hash(_), key) => Err(Object::Error(format!("unsable as hash key: {}", key))),
+            (current, _) => Err(Object::Error(format!(
+                "index operator not supported: {}",
+                current
+            ))),
         }
     }
 
-    fn eval_block_statement(&mut self, statements: BlockStatement) -> Option<Object> {
+    fn eval_block_statement(&mut self, statements: &[Spanned<Statement>]) -> Option<Object> {
         let mut result = None;
+        self.hoist_functions(statements);
 
         for statement in statements {
             match self.eval_statement(statement) {
                 Some(Object::Return(e)) => return Some(Object::Return(e)),
                 Some(Object::Error(e)) => return Some(Object::Error(e)),
+                Some(Object::Break) => return Some(Object::Break),
+                Some(Object::Continue) => return Some(Object::Continue),
                 e => result = e,
             }
         }
@@ -109,38 +272,136 @@ impl Eval {
         result
     }
 
-    fn eval_expr(&mut self, expr: Expression) -> Option<Object> {
-        match expr {
-            Expression::Ident(ident) => Some(self.eval_ident(ident)),
+    /// Loops are evaluated iteratively rather than by recursion, so they run
+    /// in constant stack however many times they repeat.
+    fn eval_while_statement(
+        &mut self,
+        condition: &Spanned<Expression>,
+        body: &[Spanned<Statement>],
+    ) -> Option<Object> {
+        loop {
+            let cond = self.eval_expr(condition).unwrap_or(Object::Null);
+            if self.is_abrupt(&cond) {
+                return Some(cond);
+            }
+            if !self.is_truthy(cond) {
+                return None;
+            }
+            if let ControlFlow::Break(result) = self.eval_loop_body(body) {
+                return result;
+            }
+        }
+    }
+
+    /// Iterates over the elements of an array, the keys of a hash or the
+    /// characters of a string.
+    fn eval_for_statement(
+        &mut self,
+        ident: &Ident,
+        iterable: &Spanned<Expression>,
+        body: &[Spanned<Statement>],
+    ) -> Option<Object> {
+        let span = iterable.span;
+        let items: Box<dyn Iterator<Item = Object>> =
+            match self.eval_expr(iterable).unwrap_or(Object::Null) {
+                Object::Array(items) => Box::new(items.into_iter()),
+                Object::Hash(hash) => Box::new(hash.into_keys()),
+                Object::String(s) => Box::new(
+                    s.chars()
+                        .map(|c| Object::String(c.to_string()))
+                        .collect::<Vec<_>>()
+                        .into_iter(),
+                ),
+                Object::Range(range) => Box::new(range.iter().map(Object::Int)),
+                obj if self.is_abrupt(&obj) => return Some(obj),
+                obj => {
+                    let err = Object::Error(format!("cannot iterate over {}", obj));
+                    return Some(self.error_at(err, span));
+                }
+            };
+
+        let Ident(name) = ident;
+        for item in items {
+            self.env.borrow_mut().set(name.clone(), item);
+            if let ControlFlow::Break(result) = self.eval_loop_body(body) {
+                return result;
+            }
+        }
+        None
+    }
+
+    /// Runs one pass of a loop body, breaking with the loop's result when
+    /// the loop should stop.
+    fn eval_loop_body(&mut self, body: &[Spanned<Statement>]) -> ControlFlow<Option<Object>> {
+        match self.eval_block_statement(body) {
+            Some(Object::Break) => ControlFlow::Break(None),
+            Some(obj @ (Object::Return(_) | Object::Error(_))) => ControlFlow::Break(Some(obj)),
+            _ => ControlFlow::Continue(()),
+        }
+    }
+
+    fn eval_expr(&mut self, expr: &Spanned<Expression>) -> Option<Object> {
+        let span = expr.span;
+        match &expr.node {
+            Expression::Ident(ident) => Some(self.eval_ident(ident, span)),
             Expression::Literal(lit) => Some(self.eval_literal(lit)),
-            Expression::Prefix(prefix, right) => self
-                .eval_expr(*right)
-                .map(|expr| self.eval_prefix_expr(prefix, expr)),
-            Expression::Infix(infix, left, right) => {
-                let left_expr = self.eval_expr(*left);
-                let right_expr = self.eval_expr(*right);
-                match left_expr.clone() {
-                    Some(l) => {
-                        if self.is_error(&l) {
-                            return left_expr;
-                        }
-                        if self.is_error(&right_expr.clone().unwrap()) {
-                            return right_expr;
-                        }
-                        right_expr.map(|r| self.eval_infix_expr(infix, left_expr.unwrap(), r))
+            Expression::Interpolated(parts) => {
+                let mut result = String::new();
+                for part in parts {
+                    match self.eval_expr(part).unwrap_or(Object::Null) {
+                        Object::String(s) => result.push_str(&s),
+                        o if self.is_abrupt(&o) => return Some(o),
+                        o => result.push_str(&o.to_string()),
                     }
-                    _ => None,
                 }
+                Some(Object::String(result))
+            }
+            Expression::Prefix(prefix, right) => {
+                let right = self.eval_expr(right)?;
+                if self.is_abrupt(&right) {
+                    return Some(right);
+                }
+                let obj = self.eval_prefix_expr(prefix.clone(), right);
+                Some(self.error_at(obj, span))
             }
+            Expression::Infix(Infix::Pipe, left, right) => Some(self.eval_pipe_expr(left, right)),
+            Expression::Infix(Infix::Coalesce, left, right) => {
+                let left = self.eval_expr(left);
+                match left {
+                    None | Some(Object::Null) => self.eval_expr(right),
+                    left => left,
+                }
+            }
+            Expression::Infix(infix @ (Infix::And | Infix::Or), left, right) => {
+                self.eval_logical_expr(infix.clone(), left, right)
+            }
+            Expression::Infix(infix, left, right) => {
+                let left = self.eval_expr(left)?;
+                if self.is_abrupt(&left) {
+                    return Some(left);
+                }
+                let right = self.eval_expr(right)?;
+                if self.is_abrupt(&right) {
+                    return Some(right);
+                }
+                let obj = self.eval_infix_expr(infix.clone(), left, right);
+                Some(self.error_at(obj, span))
+            }
+            Expression::Range {
+                start,
+                end,
+                inclusive,
+                step,
+            } => self.eval_range_expr(start, end, *inclusive, step.as_deref()),
             Expression::If {
                 condition,
                 consequence,
                 alternative,
             } => {
-                let cond_expr = match self.eval_expr(*condition) {
-                    Some(e) => e,
-                    None => return None,
-                };
+                let cond_expr = self.eval_expr(condition)?;
+                if self.is_abrupt(&cond_expr) {
+                    return Some(cond_expr);
+                }
 
                 if self.is_truthy(cond_expr) {
                     self.eval_block_statement(consequence)
@@ -150,17 +411,202 @@ impl Eval {
                     None
                 }
             }
-            Expression::Fn { params, body } => Some(Object::Fn(params, body, self.env.clone())),
-            Expression::Call { function, args } => Some(self.eval_call_expr(*function, args)),
-            Expression::Index { left, index } => {
-                let arr = self.eval_expr(*left);
-                let i = self.eval_expr(*index);
-                if arr.is_some() && i.is_some() {
-                    Some(self.eval_index_expr(arr.unwrap(), i.unwrap()))
+            Expression::Fn { params, rest, body } => Some(Object::Fn(Rc::new(Function {
+                name: None,
+                params: params.clone(),
+                rest: rest.clone(),
+                body: body.clone(),
+                env: self.env.clone(),
+            }))),
+            Expression::Call { .. } | Expression::Index { .. } | Expression::Slice { .. } => {
+                match self.eval_chain(expr) {
+                    ControlFlow::Continue(result) | ControlFlow::Break(result) => result,
+                }
+            }
+            Expression::Match { subject, arms } => {
+                let subject = self.eval_expr(subject).unwrap_or(Object::Null);
+                if self.is_abrupt(&subject) {
+                    return Some(subject);
+                }
+                self.eval_match_expr(subject, arms)
+            }
+        }
+    }
+
+    /// Builds a lazy range, turning an inclusive end into the exclusive one
+    /// that `Range` stores.
+    fn eval_range_expr(
+        &mut self,
+        start: &Spanned<Expression>,
+        end: &Spanned<Expression>,
+        inclusive: bool,
+        step: Option<&Spanned<Expression>>,
+    ) -> Option<Object> {
+        let step_span = step.map(|step| step.span);
+        let end_span = end.span;
+        let mut bounds = vec![];
+        for expr in vec![Some(start), Some(end), step].into_iter().flatten() {
+            let span = expr.span;
+            match self.eval_expr(expr)? {
+                Object::Int(i) => bounds.push(i),
+                obj if self.is_abrupt(&obj) => return Some(obj),
+                obj => {
+                    let err = Object::Error(format!("range bounds must be integers, got {}", obj));
+                    return Some(self.error_at(err, span));
+                }
+            }
+        }
+
+        let (start, mut end) = (bounds[0], bounds[1]);
+        let step = bounds.get(2).copied().unwrap_or(1);
+        if step == 0 {
+            let err = Object::Error(String::from("range step cannot be zero"));
+            return Some(self.error_at(err, step_span.unwrap_or_default()));
+        }
+        if inclusive {
+            end = match end.checked_add(step.signum()) {
+                Some(end) => end,
+                None => {
+                    let err = Object::Error(format!("range end is too large: {}", end));
+                    return Some(self.error_at(err, end_span));
+                }
+            };
+        }
+        Some(Object::Range(Range {
+            start,
+            end,
+            step,
+            inclusive,
+        }))
+    }
+
+    /// Evaluates the body of the first arm whose pattern matches `subject`
+    /// and whose guard holds, or gives nothing if no arm does. Each arm runs
+    /// in its own environment holding the pattern's bindings, so they are
+    /// gone once the arm is done or turns out not to apply.
+    fn eval_match_expr(&mut self, subject: Object, arms: &[Spanned<MatchArm>]) -> Option<Object> {
+        for arm in arms {
+            let MatchArm {
+                pattern,
+                guard,
+                body,
+            } = &arm.node;
+            let mut bindings = vec![];
+            if self
+                .match_pattern(pattern, &subject, &mut bindings)
+                .is_err()
+            {
+                continue;
+            }
+
+            let current_env = Rc::clone(&self.env);
+            let mut arm_env = Env::new_enclosed(current_env.clone());
+            for (name, value) in bindings {
+                arm_env.set(name, value);
+            }
+            self.env = Rc::new(RefCell::new(arm_env));
+            let result = self.eval_match_arm(guard.as_ref(), body);
+            self.env = current_env;
+            if let Some(result) = result {
+                return result;
+            }
+        }
+        None
+    }
+
+    /// Evaluates an arm whose pattern matched, giving `None` when its guard
+    /// doesn't hold.
+    fn eval_match_arm(
+        &mut self,
+        guard: Option<&Spanned<Expression>>,
+        body: &[Spanned<Statement>],
+    ) -> Option<Option<Object>> {
+        if let Some(guard) = guard {
+            let guard = self.eval_expr(guard).unwrap_or(Object::Null);
+            if self.is_abrupt(&guard) {
+                return Some(Some(guard));
+            }
+            if !self.is_truthy(guard) {
+                return None;
+            }
+        }
+        Some(self.eval_block_statement(body))
+    }
+
+    /// Checks whether `value` matches `pattern`, collecting the names it
+    /// binds into `bindings`, or describes why it doesn't.
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Object,
+        bindings: &mut Vec<(String, Object)>,
+    ) -> Result<(), String> {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => Ok(()),
+            (Pattern::Ident(Ident(name)), value) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(())
+            }
+            (Pattern::Literal(literal), value) => {
+                let equal = match (self.eval_literal(&literal.node), value) {
+                    (
+                        literal @ (Object::Int(_) | Object::BigInt(_) | Object::Float(_)),
+                        Object::Int(_) | Object::BigInt(_) | Object::Float(_),
+                    ) => {
+                        self.eval_infix_expr(Infix::Equal, literal, value.clone())
+                            == Object::Bool(true)
+                    }
+                    (literal, value) => literal == *value,
+                };
+                if equal {
+                    Ok(())
                 } else {
-                    None
+                    Err(format!("expected {}, got {}", literal, value))
                 }
             }
+            (Pattern::Array { items, rest }, Object::Array(values)) => {
+                match rest {
+                    Some(_) if values.len() < items.len() => {
+                        return Err(format!(
+                            "expected an array of length at least {}, got {}",
+                            items.len(),
+                            value
+                        ))
+                    }
+                    None if values.len() != items.len() => {
+                        return Err(format!(
+                            "expected an array of length {}, got {}",
+                            items.len(),
+                            value
+                        ))
+                    }
+                    _ => {}
+                }
+                for (item, value) in items.iter().zip(values) {
+                    self.match_pattern(item, value, bindings)?;
+                }
+                match rest {
+                    Some(rest) => {
+                        let rest_values = Object::Array(values[items.len()..].to_vec());
+                        self.match_pattern(rest, &rest_values, bindings)
+                    }
+                    None => Ok(()),
+                }
+            }
+            (Pattern::Hash(pairs), Object::Hash(hash)) => {
+                for (key, pattern) in pairs {
+                    let key = self.eval_literal(&key.node);
+                    match hash.get(&key) {
+                        Some(value) => self.match_pattern(pattern, value, bindings)?,
+                        None => return Err(format!("key not found: {}", key)),
+                    }
+                }
+                Ok(())
+            }
+            (Pattern::Array { .. }, value) => {
+                Err(format!("cannot destructure {} as an array", value))
+            }
+            (Pattern::Hash(_), value) => Err(format!("cannot destructure {} as a hash", value)),
         }
     }
 
@@ -172,6 +618,7 @@ impl Eval {
             Prefix::Not => self.eval_not_prefix_expr(expr),
             Prefix::Minus => self.eval_minus_prefix_expr(expr),
             Prefix::Plus => self.eval_plus_prefix_expr(expr),
+            Prefix::BitNot => self.eval_bit_not_prefix_expr(expr),
         }
     }
 
@@ -186,7 +633,12 @@ impl Eval {
 
     fn eval_minus_prefix_expr(&mut self, expr: Object) -> Object {
         match expr {
-            Object::Int(i) => Object::Int(-i),
+            Object::Int(i) => match i.checked_neg() {
+                Some(i) => Object::Int(i),
+                None => Object::from(-BigInt::from(i)),
+            },
+            Object::BigInt(i) => Object::from(-i),
+            Object::Float(f) => Object::Float(-f),
             _ => Object::Error(format!("unknown operator: -{}", expr)),
         }
     }
@@ -194,30 +646,75 @@ impl Eval {
     fn eval_plus_prefix_expr(&mut self, expr: Object) -> Object {
         match expr {
             Object::Int(i) => Object::Int(i),
+            Object::BigInt(i) => Object::BigInt(i),
+            Object::Float(f) => Object::Float(f),
             _ => Object::Error(format!("unknown operator: {}", expr)),
         }
     }
 
+    fn eval_bit_not_prefix_expr(&mut self, expr: Object) -> Object {
+        match expr {
+            Object::Int(i) => Object::Int(!i),
+            Object::BigInt(i) => Object::from(!i),
+            _ => Object::Error(format!("unknown operator: ~{}", expr)),
+        }
+    }
+
+    /// Numbers can be mixed freely: an int is promoted to a big int when the
+    /// other operand is one, and any number is promoted to a float when the
+    /// other operand is a float. Only integer division truncates.
     fn eval_infix_expr(&mut self, infix: Infix, left: Object, right: Object) -> Object {
-        match left {
-            Object::Int(left_expr) => {
-                if let Object::Int(right_expr) = right {
-                    self.eval_int_infix_expr(infix, left_expr, right_expr)
-                } else {
-                    Object::Error(format!("type mismatch: {} {} {}", left, infix, right))
-                }
+        match (left, right) {
+            (Object::Int(left), Object::Int(right)) => self.eval_int_infix_expr(infix, left, right),
+            (
+                left @ (Object::Int(_) | Object::BigInt(_)),
+                right @ (Object::Int(_) | Object::BigInt(_)),
+            ) => self.eval_big_int_infix_expr(infix, to_big_int(left), to_big_int(right)),
+            (
+                left @ (Object::Int(_) | Object::BigInt(_) | Object::Float(_)),
+                right @ (Object::Int(_) | Object::BigInt(_) | Object::Float(_)),
+            ) => self.eval_float_infix_expr(infix, to_float(&left), to_float(&right)),
+            (Object::String(left), Object::String(right)) => {
+                self.eval_string_infix_expr(infix, left, right)
             }
-            Object::String(left_expr) => {
-                if let Object::String(right_expr) = right {
-                    self.eval_string_infix_expr(infix, left_expr, right_expr)
-                } else {
-                    Object::Error(format!("type mismatch: {} {} {}", left_expr, infix, right))
-                }
+            (left @ Object::Null, right) | (left, right @ Object::Null)
+                if matches!(infix, Infix::Equal | Infix::NotEqual) =>
+            {
+                Object::Bool((left == right) == (infix == Infix::Equal))
+            }
+            (
+                left @ (Object::Int(_) | Object::BigInt(_) | Object::Float(_) | Object::String(_)),
+                right,
+            ) => Object::Error(format!("type mismatch: {} {} {}", left, infix, right)),
+            (left, right) => {
+                Object::Error(format!("unknown operator: {} {} {}", left, infix, right))
             }
-            _ => Object::Error(format!("unknown operator: {} {} {}", left, infix, right)),
         }
     }
 
+    /// Evaluates `&&` and `||`, only evaluating the right-hand side when the
+    /// left-hand side doesn't already decide the result.
+    fn eval_logical_expr(
+        &mut self,
+        infix: Infix,
+        left: &Spanned<Expression>,
+        right: &Spanned<Expression>,
+    ) -> Option<Object> {
+        let left = self.eval_expr(left)?;
+        if self.is_abrupt(&left) {
+            return Some(left);
+        }
+        let left = self.is_truthy(left);
+        if left == (infix == Infix::Or) {
+            return Some(Object::Bool(left));
+        }
+        let right = self.eval_expr(right)?;
+        if self.is_abrupt(&right) {
+            return Some(right);
+        }
+        Some(Object::Bool(self.is_truthy(right)))
+    }
+
     fn eval_string_infix_expr(&mut self, infix: Infix, left: String, right: String) -> Object {
         match infix {
             Infix::Plus => Object::String(format!("{}{}", left, right)),
@@ -225,29 +722,298 @@ impl Eval {
         }
     }
 
-    fn eval_int_infix_expr(&mut self, infix: Infix, left: i32, right: i32) -> Object {
+    /// Integer arithmetic that overflows an `i64` is redone with big ints, as
+    /// are shifts and powers with a negative right-hand side so that they
+    /// report the error.
+    fn eval_int_infix_expr(&mut self, infix: Infix, left: i64, right: i64) -> Object {
+        if right == 0 {
+            if let Some(err) = self.zero_divisor_error(&infix) {
+                return err;
+            }
+        }
+
+        let result = match infix {
+            Infix::Plus => left.checked_add(right),
+            Infix::Minus => left.checked_sub(right),
+            Infix::Multiply => left.checked_mul(right),
+            Infix::Divide => left.checked_div(right),
+            Infix::Modulus => left.checked_rem(right),
+            Infix::BitAnd => Some(left & right),
+            Infix::BitOr => Some(left | right),
+            Infix::BitXor => Some(left ^ right),
+            Infix::ShiftLeft => right
+                .to_u32()
+                .filter(|&n| n < 64)
+                .map(|n| left << n)
+                .filter(|&i| i >> right == left),
+            Infix::ShiftRight => right.to_u32().map(|n| left >> n.min(63)),
+            Infix::Power => right.to_u32().and_then(|n| left.checked_pow(n)),
+            Infix::LessThan => return Object::Bool(left < right),
+            Infix::LessThanEqual => return Object::Bool(left <= right),
+            Infix::GreaterThan => return Object::Bool(left > right),
+            Infix::GreaterThanEqual => return Object::Bool(left >= right),
+            Infix::Equal => return Object::Bool(left == right),
+            Infix::NotEqual => return Object::Bool(left != right),
+            Infix::And | Infix::Or | Infix::Pipe | Infix::Coalesce => {
+                return Object::Error(format!("unknown operator: {} {} {}", left, infix, right))
+            }
+        };
+
+        match result {
+            Some(i) => Object::Int(i),
+            None => self.eval_big_int_infix_expr(infix, BigInt::from(left), BigInt::from(right)),
+        }
+    }
+
+    fn eval_big_int_infix_expr(&mut self, infix: Infix, left: BigInt, right: BigInt) -> Object {
+        if right.is_zero() {
+            if let Some(err) = self.zero_divisor_error(&infix) {
+                return err;
+            }
+        }
+
         match infix {
-            Infix::Plus => Object::Int(left + right),
-            Infix::Minus => Object::Int(left - right),
-            Infix::Multiply => Object::Int(left * right),
-            Infix::Divide => Object::Int(left / right),
-            Infix::Modulus => Object::Int(left % right),
+            Infix::Plus => Object::from(left + right),
+            Infix::Minus => Object::from(left - right),
+            Infix::Multiply => Object::from(left * right),
+            Infix::Divide => Object::from(left / right),
+            Infix::Modulus => Object::from(left % right),
+            Infix::BitAnd => Object::from(left & right),
+            Infix::BitOr => Object::from(left | right),
+            Infix::BitXor => Object::from(left ^ right),
+            Infix::ShiftLeft | Infix::ShiftRight | Infix::Power => {
+                let n = match self.shift_or_exponent(&infix, &left, &right) {
+                    Ok(n) => n,
+                    Err(err) => return err,
+                };
+                match infix {
+                    Infix::ShiftLeft => Object::from(left << n),
+                    Infix::ShiftRight => Object::from(left >> n),
+                    _ => Object::from(left.pow(n)),
+                }
+            }
             Infix::LessThan => Object::Bool(left < right),
             Infix::LessThanEqual => Object::Bool(left <= right),
             Infix::GreaterThan => Object::Bool(left > right),
             Infix::GreaterThanEqual => Object::Bool(left >= right),
             Infix::Equal => Object::Bool(left == right),
             Infix::NotEqual => Object::Bool(left != right),
+            Infix::And | Infix::Or | Infix::Pipe | Infix::Coalesce => {
+                Object::Error(format!("unknown operator: {} {} {}", left, infix, right))
+            }
+        }
+    }
+
+    fn eval_float_infix_expr(&mut self, infix: Infix, left: f64, right: f64) -> Object {
+        if right == 0.0 {
+            if let Some(err) = self.zero_divisor_error(&infix) {
+                return err;
+            }
+        }
+
+        match infix {
+            Infix::Plus => Object::Float(left + right),
+            Infix::Minus => Object::Float(left - right),
+            Infix::Multiply => Object::Float(left * right),
+            Infix::Divide => Object::Float(left / right),
+            Infix::Modulus => Object::Float(left % right),
+            Infix::Power => Object::Float(left.powf(right)),
+            Infix::LessThan => Object::Bool(left < right),
+            Infix::LessThanEqual => Object::Bool(left <= right),
+            Infix::GreaterThan => Object::Bool(left > right),
+            Infix::GreaterThanEqual => Object::Bool(left >= right),
+            Infix::Equal => Object::Bool(left == right),
+            Infix::NotEqual => Object::Bool(left != right),
+            Infix::And
+            | Infix::Or
+            | Infix::Pipe
+            | Infix::Coalesce
+            | Infix::BitAnd
+            | Infix::BitOr
+            | Infix::BitXor
+            | Infix::ShiftLeft
+            | Infix::ShiftRight => {
+                Object::Error(format!("unknown operator: {} {} {}", left, infix, right))
+            }
+        }
+    }
+
+    /// Returns the right-hand side of a shift or `**` as a `u32`, or the
+    /// error for a negative one or one whose result would be too large.
+    fn shift_or_exponent(
+        &mut self,
+        infix: &Infix,
+        left: &BigInt,
+        right: &BigInt,
+    ) -> Result<u32, Object> {
+        if right.is_negative() {
+            let operand = match infix {
+                Infix::Power => "exponent",
+                _ => "shift amount",
+            };
+            return Err(Object::Error(format!("negative {}: {}", operand, right)));
+        }
+
+        let bits = match infix {
+            // shifting right only ever shrinks the number
+            Infix::ShiftRight => return Ok(right.to_u32().unwrap_or(u32::MAX)),
+            Infix::ShiftLeft => right.to_u64().map(|n| left.bits().saturating_add(n)),
+            _ => right
+                .to_u64()
+                .map(|n| left.bits().saturating_sub(1).saturating_mul(n)),
+        };
+        match (right.to_u32(), bits) {
+            (Some(n), Some(bits)) if bits <= MAX_INT_BITS => Ok(n),
+            _ => Err(Object::Error(format!(
+                "integer overflow: {} {} {}",
+                left, infix, right
+            ))),
+        }
+    }
+
+    /// Returns the error for using `infix` with a zero right-hand side, if
+    /// that is an error for the operator.
+    fn zero_divisor_error(&mut self, infix: &Infix) -> Option<Object> {
+        match infix {
+            Infix::Divide => Some(Object::Error(String::from("division by zero"))),
+            Infix::Modulus => Some(Object::Error(String::from("modulo by zero"))),
+            _ => None,
         }
     }
 
-    fn eval_call_expr(&mut self, function: Expression, args: Vec<Expression>) -> Object {
-        let args = args
-            .iter()
-            .map(|a| self.eval_expr(a.clone()).unwrap_or(Object::Null))
-            .collect::<Vec<_>>();
+    /// Evaluates a call, index or slice together with the chain of them
+    /// that leads up to it. The chain breaks with a step's result when an
+    /// optional step finds `null` or a step gives an error or jump, skipping
+    /// the steps after it, so that `a?["b"]["c"]` is `null` when `a` is.
+    fn eval_chain(
+        &mut self,
+        expr: &Spanned<Expression>,
+    ) -> ControlFlow<Option<Object>, Option<Object>> {
+        let span = expr.span;
+        let result = match &expr.node {
+            Expression::Call {
+                function,
+                args,
+                kwargs,
+                optional,
+            } => match self.eval_chain_target(function, *optional)? {
+                Some(f) => {
+                    let name = call_name(function);
+                    Some(self.eval_call_expr(name, f, None, args, kwargs, span))
+                }
+                None => Some(Object::Null),
+            },
+            Expression::Index {
+                left,
+                index,
+                optional,
+            } => match self.eval_chain_target(left, *optional)? {
+                Some(left) => self.eval_index_step(left, index, span),
+                None => None,
+            },
+            Expression::Slice {
+                left,
+                start,
+                end,
+                step,
+                optional,
+            } => match self.eval_chain_target(left, *optional)? {
+                Some(left) => {
+                    let parts = [start.as_deref(), end.as_deref(), step.as_deref()];
+                    self.eval_slice_expr(left, parts, span)
+                }
+                None => None,
+            },
+            _ => self.eval_expr(expr),
+        };
+        ControlFlow::Continue(result)
+    }
 
-        self.apply_function(function, args)
+    /// Evaluates what a step of a chain applies to, breaking out of the
+    /// chain as `eval_chain` describes.
+    fn eval_chain_target(
+        &mut self,
+        target: &Spanned<Expression>,
+        optional: bool,
+    ) -> ControlFlow<Option<Object>, Option<Object>> {
+        match self.eval_chain(target)? {
+            Some(obj) if self.is_abrupt(&obj) => ControlFlow::Break(Some(obj)),
+            Some(Object::Null) if optional => ControlFlow::Break(Some(Object::Null)),
+            target => ControlFlow::Continue(target),
+        }
+    }
+
+    fn eval_index_step(
+        &mut self,
+        left: Object,
+        index: &Spanned<Expression>,
+        span: Span,
+    ) -> Option<Object> {
+        let index = self.eval_expr(index)?;
+        if self.is_abrupt(&index) {
+            return Some(index);
+        }
+        let obj = self.eval_index_expr(left, index);
+        Some(self.error_at(obj, span))
+    }
+
+    /// Calls `function`, written as `name` at the call site, with `piped`
+    /// ahead of the arguments in the call.
+    fn eval_call_expr(
+        &mut self,
+        name: String,
+        function: Object,
+        piped: Option<Object>,
+        args: &[Spanned<Expression>],
+        kwargs: &[(Ident, Spanned<Expression>)],
+        span: Span,
+    ) -> Object {
+        let args = match self.eval_exprs(args) {
+            Ok(args) => piped.into_iter().chain(args).collect::<Vec<_>>(),
+            Err(err) => return err,
+        };
+        let mut values = vec![];
+        for (name, a) in kwargs {
+            let value = self.eval_expr(a).unwrap_or(Object::Null);
+            if self.is_abrupt(&value) {
+                return value;
+            }
+            values.push((name.clone(), value));
+        }
+        let kwargs = values;
+
+        self.apply_function(name, function, args, kwargs, span)
+    }
+
+    /// Calls the right-hand side of `|>` with the left-hand side as its
+    /// first argument, ahead of any arguments written in the call.
+    fn eval_pipe_expr(
+        &mut self,
+        left: &Spanned<Expression>,
+        right: &Spanned<Expression>,
+    ) -> Object {
+        let value = self.eval_expr(left).unwrap_or(Object::Null);
+        if self.is_abrupt(&value) {
+            return value;
+        }
+        let span = right.span;
+        let (function, args, kwargs, optional) = match &right.node {
+            Expression::Call {
+                function,
+                args,
+                kwargs,
+                optional,
+            } => (&**function, &args[..], &kwargs[..], *optional),
+            _ => (right, &[][..], &[][..], false),
+        };
+        match self.eval_chain_target(function, optional) {
+            ControlFlow::Continue(Some(f)) => {
+                let name = call_name(function);
+                self.eval_call_expr(name, f, Some(value), args, kwargs, span)
+            }
+            ControlFlow::Continue(None) => Object::Null,
+            ControlFlow::Break(result) => result.unwrap_or(Object::Null),
+        }
     }
 
     fn eval_index_expr(&mut self, left: Object, index: Object) -> Object {
@@ -259,11 +1025,38 @@ impl Eval {
                     Object::Error(format!("index operator not supported: {}", left))
                 }
             }
+            Object::String(ref s) => match index {
+                Object::Int(i) => {
+                    let index = if i < 0 {
+                        s.chars().count() as i64 + i
+                    } else {
+                        i
+                    };
+                    let ch = index.to_usize().and_then(|i| s.chars().nth(i));
+                    match ch {
+                        Some(ch) => Object::String(ch.to_string()),
+                        None => Object::Null,
+                    }
+                }
+                _ => Object::Error(format!("index operator not supported: {}", left)),
+            },
+            Object::Range(range) => match index {
+                Object::Int(i) => {
+                    let index = if i < 0 { range.len() + i } else { i };
+                    match range.get(index) {
+                        Some(item) => Object::Int(item),
+                        None => Object::Null,
+                    }
+                }
+                _ => Object::Error(format!("index operator not supported: {}", left)),
+            },
             Object::Hash(ref hash) => match index {
-                Object::Int(_) | Object::Bool(_) | Object::String(_) => match hash.get(&index) {
-                    Some(o) => o.clone(),
-                    None => Object::Null,
-                },
+                Object::Int(_) | Object::BigInt(_) | Object::Bool(_) | Object::String(_) => {
+                    match hash.get(&index) {
+                        Some(o) => o.clone(),
+                        None => Object::Null,
+                    }
+                }
                 Object::Error(_) => index,
                 _ => Object::Error(format!("unsable as hash key: {}", index)),
             },
@@ -271,14 +1064,70 @@ impl Eval {
         }
     }
 
-    fn eval_array_index_expr(&mut self, array: Vec<Object>, index: i32) -> Object {
-        let max = array.len() as i32;
+    /// Evaluates `left[start:end:step]`, given the three parts of the slice
+    /// in that order.
+    fn eval_slice_expr(
+        &mut self,
+        left: Object,
+        parts: [Option<&Spanned<Expression>>; 3],
+        span: Span,
+    ) -> Option<Object> {
+        let mut bounds = vec![];
+        for part in parts {
+            let part = match part {
+                Some(part) => part,
+                None => {
+                    bounds.push(None);
+                    continue;
+                }
+            };
+            let part_span = part.span;
+            match self.eval_expr(part)? {
+                Object::Int(i) => bounds.push(Some(i)),
+                obj if self.is_abrupt(&obj) => return Some(obj),
+                obj => {
+                    let err = Object::Error(format!("slice bounds must be integers, got {}", obj));
+                    return Some(self.error_at(err, part_span));
+                }
+            }
+        }
+        let obj = self.slice(left, bounds[0], bounds[1], bounds[2]);
+        Some(self.error_at(obj, span))
+    }
+
+    fn slice(
+        &mut self,
+        left: Object,
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    ) -> Object {
+        let step = step.unwrap_or(1);
+        if step == 0 {
+            return Object::Error(String::from("slice step cannot be zero"));
+        }
+        match left {
+            Object::Array(arr) => {
+                let indices = slice_indices(arr.len(), start, end, step);
+                Object::Array(indices.map(|i| arr[i].clone()).collect())
+            }
+            Object::String(s) => {
+                let chars: Vec<char> = s.chars().collect();
+                let indices = slice_indices(chars.len(), start, end, step);
+                Object::String(indices.map(|i| chars[i]).collect())
+            }
+            _ => Object::Error(format!("slice operator not supported: {}", left)),
+        }
+    }
+
+    fn eval_array_index_expr(&mut self, array: Vec<Object>, index: i64) -> Object {
+        let max = array.len() as i64;
         if index > max {
             return Object::Null;
         }
 
         if index < 0 {
-            match array.get((array.len() as i32 + index) as usize) {
+            match array.get((array.len() as i64 + index) as usize) {
                 Some(o) => return o.clone(),
                 None => return Object::Null,
             }
@@ -289,45 +1138,116 @@ impl Eval {
         }
     }
 
-    fn apply_function(&mut self, function: Expression, args: Vec<Object>) -> Object {
-        let (params, body, env) = match self.eval_expr(function) {
-            Some(Object::Fn(params, body, env)) => (params, body, env),
-            Some(Object::Builtin(func)) => return func(args),
-            Some(o) => return Object::Error(format!("function not found: {}", o)),
-            None => return Object::Null,
-        };
-
-        if params.len() != args.len() {
-            return Object::Error(format!(
-                "expected arguments: {}\ngiven arguments: {}",
-                params.len(),
-                args.len()
-            ));
+    /// Calls `function`, which was written as `name` at the call site.
+    fn apply_function(
+        &mut self,
+        mut name: String,
+        function: Object,
+        args: Vec<Object>,
+        kwargs: Vec<(Ident, Object)>,
+        span: Span,
+    ) -> Object {
+        let function = match function {
+            Object::Fn(function) => {
+                if let Some(Ident(ref declared)) = function.name {
+                    name = format!("`{}`", declared);
+                }
+                function
+            }
+            Object::Builtin(func) => {
+                let obj = if kwargs.is_empty() {
+                    func(args)
+                } else {
+                    Object::Error(format!("{} does not take keyword arguments", name))
+                };
+                return self.error_at(obj, span);
+            }
+            o @ Object::Error(_) => return o,
+            o => {
+                let err = Object::Error(format!("function not found: {}", o));
+                return self.error_at(err, span);
+            }
         };
 
         let current_env = Rc::clone(&self.env);
-        let extended_env = self.extended_function_env(params, env, args);
-        self.env = Rc::new(RefCell::new(extended_env));
-        let evaluated = self.eval_block_statement(body);
+        self.env = Rc::new(RefCell::new(Env::new_enclosed(function.env.clone())));
+        let evaluated = match self.bind_arguments(&name, &function, args, kwargs, span) {
+            Some(err) => Some(err),
+            None => self.eval_block_statement(&function.body),
+        };
         self.env = current_env;
-        //println!("{:#?}", self.env);
         self.unwrap_return_value(evaluated)
     }
 
-    fn extended_function_env(
+    /// Binds a call's arguments to the function's parameters in the current
+    /// environment. A parameter without an argument takes its default, which
+    /// is evaluated once the parameters before it are bound so that it can
+    /// refer to them.
+    fn bind_arguments(
         &mut self,
-        params: Vec<Ident>,
-        env: Rc<RefCell<Env>>,
+        name: &str,
+        function: &Function,
         args: Vec<Object>,
-    ) -> Env {
-        let mut scope_env = Env::new_enclosed(env);
+        kwargs: Vec<(Ident, Object)>,
+        span: Span,
+    ) -> Option<Object> {
+        let params = &function.params;
+        if function.rest.is_none() && args.len() > params.len() {
+            let err = Object::Error(format!(
+                "too many arguments for {}: expected at most {}, got {}",
+                name,
+                params.len(),
+                args.len()
+            ));
+            return Some(self.error_at(err, span));
+        }
 
-        for (ident, arg) in params.iter().zip(args.iter()) {
-            let Ident(name) = ident.clone();
-            scope_env.set(name, arg.to_owned());
+        let mut args = args.into_iter();
+        let mut values: Vec<Option<Object>> = params.iter().map(|_| args.next()).collect();
+        for (Ident(key), value) in kwargs {
+            match params.iter().position(|param| param.name.0 == key) {
+                Some(i) if values[i].is_none() => values[i] = Some(value),
+                Some(_) => {
+                    let err = Object::Error(format!(
+                        "multiple values for parameter `{}` of {}",
+                        key, name
+                    ));
+                    return Some(self.error_at(err, span));
+                }
+                None => {
+                    let err = Object::Error(format!("unknown parameter `{}` for {}", key, name));
+                    return Some(self.error_at(err, span));
+                }
+            }
         }
 
-        scope_env
+        for (param, value) in params.iter().zip(values) {
+            let Ident(ref param_name) = param.name;
+            let value = match (value, &param.default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => {
+                    let value = self.eval_expr(default).unwrap_or(Object::Null);
+                    if self.is_abrupt(&value) {
+                        return Some(value);
+                    }
+                    value
+                }
+                (None, None) => {
+                    let err = Object::Error(format!(
+                        "missing argument for parameter `{}` of {}",
+                        param_name, name
+                    ));
+                    return Some(self.error_at(err, span));
+                }
+            };
+            self.env.borrow_mut().set(param_name.clone(), value);
+        }
+        if let Some(Ident(ref rest)) = function.rest {
+            self.env
+                .borrow_mut()
+                .set(rest.clone(), Object::Array(args.collect()));
+        }
+        None
     }
 
     fn extend_global_env(&mut self, lib: String) -> Option<Object> {
@@ -335,11 +1255,12 @@ impl Eval {
             Some(e) => e,
             None => return Some(Object::Error(format!("Could not load lib: {}", lib))),
         };
-        let mut new_env = Env::new_enclosed(self.env.clone());
+        // The library is bound in place rather than in a new environment, so
+        // that functions already holding this one, like hoisted declarations,
+        // see it too.
         for (k, v) in lib_env {
-            new_env.set(k, v);
+            self.env.borrow_mut().set(k, v);
         }
-        self.env = Rc::new(RefCell::new(new_env));
         None
     }
 
@@ -351,43 +1272,63 @@ impl Eval {
         }
     }
 
-    fn eval_ident(&mut self, ident: Ident) -> Object {
+    fn eval_ident(&mut self, ident: &Ident, span: Span) -> Object {
         let Ident(i) = ident;
         let builtins = new_builtins();
-        if builtins.contains_key(&i) {
-            return builtins.get(&i).unwrap().clone();
+        if builtins.contains_key(i) {
+            return builtins.get(i).unwrap().clone();
         };
-        match self.env.borrow_mut().get(&i) {
+        let value = self.env.borrow_mut().get(i);
+        match value {
             Some(i) => i,
-            None => Object::Error(format!("identifier not found: {}", i)),
+            None => {
+                let err = Object::Error(format!("identifier not found: {}", i));
+                self.error_at(err, span)
+            }
         }
     }
 
-    fn eval_literal(&mut self, lit: Literal) -> Object {
+    fn eval_literal(&mut self, lit: &Literal) -> Object {
         match lit {
-            Literal::String(s) => Object::String(s),
-            Literal::Int(i) => Object::Int(i),
-            Literal::Bool(b) => Object::Bool(b),
-            Literal::Array(a) => Object::Array(
-                a.iter()
-                    .map(|e| self.eval_expr(e.clone()).unwrap_or(Object::Null))
-                    .collect::<Vec<_>>(),
-            ),
+            Literal::String(s) => Object::String(s.clone()),
+            Literal::Int(i) => Object::Int(*i),
+            Literal::BigInt(i) => Object::from(i.clone()),
+            Literal::Float(f) => Object::Float(*f),
+            Literal::Bool(b) => Object::Bool(*b),
+            Literal::Null => Object::Null,
+            Literal::Array(a) => match self.eval_exprs(a) {
+                Ok(items) => Object::Array(items),
+                Err(err) => err,
+            },
             Literal::Hash(h) => self.eval_hash_literal(h),
         }
     }
 
-    fn eval_hash_literal(&mut self, h: Vec<(Expression, Expression)>) -> Object {
+    /// Evaluates `exprs` in order, stopping at the first error or jump.
+    fn eval_exprs(&mut self, exprs: &[Spanned<Expression>]) -> Result<Vec<Object>, Object> {
+        let mut values = Vec::with_capacity(exprs.len());
+        for e in exprs {
+            let val = self.eval_expr(e).unwrap_or(Object::Null);
+            if self.is_abrupt(&val) {
+                return Err(val);
+            }
+            values.push(val);
+        }
+        Ok(values)
+    }
+
+    #[allow(clippy::mutable_key_type)]
+    fn eval_hash_literal(&mut self, h: &[(Spanned<Expression>, Spanned<Expression>)]) -> Object {
         let mut hash = HashMap::new();
 
         for (k, v) in h {
             let key = self.eval_expr(k).unwrap_or(Object::Null);
-            if self.is_error(&key) {
+            if self.is_abrupt(&key) {
                 return key;
             }
 
             let val = self.eval_expr(v).unwrap_or(Object::Null);
-            if self.is_error(&val) {
+            if self.is_abrupt(&val) {
                 return val;
             }
 
@@ -396,3 +1337,60 @@ impl Eval {
         Object::Hash(hash)
     }
 }
+
+fn to_big_int(obj: Object) -> BigInt {
+    match obj {
+        Object::Int(i) => BigInt::from(i),
+        Object::BigInt(i) => i,
+        _ => BigInt::default(),
+    }
+}
+
+fn to_float(obj: &Object) -> f64 {
+    match obj {
+        Object::Int(i) => *i as f64,
+        Object::BigInt(i) => i.to_f64().unwrap_or(f64::NAN),
+        Object::Float(f) => *f,
+        _ => f64::NAN,
+    }
+}
+
+/// The indices a `[start:end:step]` slice picks out of a sequence of `len`
+/// items. Negative bounds count back from the end, and bounds past either
+/// end are clamped, so a slice never fails for being out of range. A
+/// negative step walks backwards, starting from the last item by default.
+fn slice_indices(
+    len: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: i64,
+) -> impl Iterator<Item = usize> {
+    let len = len as i128;
+    let step = step as i128;
+    let (lowest, highest) = if step > 0 { (0, len) } else { (-1, len - 1) };
+    let bound = |bound: Option<i64>, default: i128| match bound {
+        Some(i) if i < 0 => (i as i128 + len).clamp(lowest, highest),
+        Some(i) => (i as i128).clamp(lowest, highest),
+        None => default,
+    };
+    let (start, end) = if step > 0 {
+        (bound(start, 0), bound(end, len))
+    } else {
+        (bound(start, len - 1), bound(end, -1))
+    };
+
+    let count = if step > 0 {
+        (end - start + step - 1) / step
+    } else {
+        (start - end - step - 1) / -step
+    };
+    (0..count.max(0)).map(move |i| (start + i * step) as usize)
+}
+
+/// How a call to `function` is referred to in error messages.
+fn call_name(function: &Spanned<Expression>) -> String {
+    match function.node {
+        Expression::Ident(Ident(ref name)) => format!("`{}`", name),
+        _ => String::from("anonymous function"),
+    }
+}
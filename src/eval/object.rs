@@ -1,5 +1,7 @@
 use super::env::Env;
-use crate::ast::{BlockStatement, Ident};
+use crate::ast::{BlockStatement, Ident, Param};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 use std::{
     cell::RefCell,
     collections::HashMap,
@@ -10,40 +12,124 @@ use std::{
 
 pub type BuiltlnFn = fn(Vec<Object>) -> Object;
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(PartialEq, Clone, Debug)]
 pub enum Object {
-    Int(i32),
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
     String(String),
     Bool(bool),
     Null,
     Return(Box<Object>),
+    /// Signals a `break` or `continue` on its way to the enclosing loop.
+    Break,
+    Continue,
     Error(String),
-    Fn(Vec<Ident>, BlockStatement, Rc<RefCell<Env>>),
+    Fn(Rc<Function>),
+    Range(Range),
     Builtin(BuiltlnFn),
     Array(Vec<Object>),
+    #[allow(clippy::mutable_key_type)]
     Hash(HashMap<Object, Object>),
 }
 
+/// A closure over `env`. Functions from a `fn name(...)` declaration
+/// remember their name for display and error messages.
+#[derive(PartialEq, Debug)]
+pub struct Function {
+    pub name: Option<Ident>,
+    pub params: Vec<Param>,
+    pub rest: Option<Ident>,
+    pub body: BlockStatement,
+    pub env: Rc<RefCell<Env>>,
+}
+
+/// The integers from `start` up to but not including `end`, counting by
+/// `step`, which may be negative. Items are computed when they are needed
+/// rather than stored. `inclusive` records that the range was written with
+/// `..=`, so that it is displayed the way it was written.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub inclusive: bool,
+}
+
+impl Range {
+    pub fn len(&self) -> i64 {
+        let (start, end, step) = (self.start as i128, self.end as i128, self.step as i128);
+        let distance = if step > 0 { end - start } else { start - end };
+        if distance <= 0 {
+            return 0;
+        }
+        let step = step.abs();
+        ((distance + step - 1) / step).min(i64::MAX as i128) as i64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: i64) -> Option<i64> {
+        if index < 0 || index >= self.len() {
+            return None;
+        }
+        Some((self.start as i128 + index as i128 * self.step as i128) as i64)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> {
+        let range = *self;
+        (0..range.len()).filter_map(move |i| range.get(i))
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.inclusive {
+            write!(f, "{}..={}", self.start, self.end - self.step.signum())?;
+        } else {
+            write!(f, "{}..{}", self.start, self.end)?;
+        }
+        if self.step != 1 {
+            write!(f, " step {}", self.step)?;
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Object::Int(ref value) => write!(f, "{}", value),
+            Object::BigInt(ref value) => write!(f, "{}", value),
+            Object::Float(ref value) => write!(f, "{:?}", value),
             Object::String(ref value) => write!(f, "{}", value),
             Object::Bool(ref value) => write!(f, "{}", value),
             Object::Null => write!(f, "null"),
             Object::Return(ref value) => write!(f, "{}", value),
+            Object::Break => write!(f, "break"),
+            Object::Continue => write!(f, "continue"),
             Object::Error(ref value) => write!(f, "{}", value),
-            Object::Fn(ref params, _, _) => {
-                let mut result = String::new();
-                for (i, Ident(ref s)) in params.iter().enumerate() {
-                    if i < 1 {
-                        result.push_str(s);
-                    } else {
-                        result.push_str(&format!(", {}", s));
-                    }
+            Object::Fn(ref function) => {
+                let mut result: Vec<String> = function
+                    .params
+                    .iter()
+                    .map(|param| match param.default {
+                        Some(ref default) => format!("{} = {}", param.name, default),
+                        None => param.name.to_string(),
+                    })
+                    .collect();
+                if let Some(ref rest) = function.rest {
+                    result.push(format!("...{}", rest));
+                }
+                match function.name {
+                    Some(ref name) => write!(f, "fn {}({}) {{ ... }}", name, result.join(", ")),
+                    None => write!(f, "fn({}) {{ ... }}", result.join(", ")),
                 }
-                write!(f, "fn({}) {{ ... }}", result)
             }
+            Object::Range(ref range) => write!(f, "{}", range),
             Object::Builtin(_) => write!(f, "[builtin fn]"),
             Object::Array(ref val) => {
                 let mut result = String::new();
@@ -72,12 +158,24 @@ impl fmt::Display for Object {
     }
 }
 
+/// Integers are kept as `Object::Int` whenever they fit in an `i64`, so only
+/// values outside that range are ever `Object::BigInt`.
+impl From<BigInt> for Object {
+    fn from(i: BigInt) -> Self {
+        match i.to_i64() {
+            Some(i) => Object::Int(i),
+            None => Object::BigInt(i),
+        }
+    }
+}
+
 impl Eq for Object {}
 
 impl Hash for Object {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match *self {
             Object::Int(ref i) => i.hash(state),
+            Object::BigInt(ref i) => i.hash(state),
             Object::Bool(ref b) => b.hash(state),
             Object::String(ref s) => s.hash(state),
             _ => "".hash(state),
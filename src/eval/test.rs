@@ -1,8 +1,12 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use super::{env::Env, object::Object, Eval};
+use super::{
+    env::Env,
+    object::{Function, Object, Range},
+    Eval,
+};
 use crate::{
-    ast::{Expression, Ident, Infix, Literal, Statement},
+    ast::{Expression, Ident, Infix, Literal, Param, Statement},
     lexer::Lexer,
     parser::Parser,
 };
@@ -61,6 +65,31 @@ fn test_string_concatenation_eval() {
     test!(tests);
 }
 
+#[test]
+fn test_string_interpolation_eval() {
+    let tests = vec![
+        (
+            r#"let name = "Ada"; "Hello ${name}!""#,
+            Some(Object::String(String::from("Hello Ada!"))),
+        ),
+        (
+            r#"let items = [1, 2]; "${len(items)} items: ${items}""#,
+            Some(Object::String(String::from("2 items: [1, 2]"))),
+        ),
+        (
+            r#""${true} ${1 + 2 * 3} ${"nested"}""#,
+            Some(Object::String(String::from("true 7 nested"))),
+        ),
+        (
+            r#""x: ${1 + true}""#,
+            Some(Object::Error(String::from(
+                "type mismatch: 1 + true at line 1, column 7",
+            ))),
+        ),
+    ];
+    test!(tests);
+}
+
 #[test]
 fn test_not_prefix_eval() {
     let tests = vec![
@@ -100,6 +129,212 @@ fn test_int_infix_eval() {
     test!(tests);
 }
 
+#[test]
+fn test_big_int_eval() {
+    let big = |s: &str| Some(Object::BigInt(s.parse().unwrap()));
+    let tests = vec![
+        ("2147483647 + 1", Some(Object::Int(2147483648))),
+        ("9223372036854775807 + 1", big("9223372036854775808")),
+        ("-9223372036854775807 - 2", big("-9223372036854775809")),
+        ("4294967296 * 4294967296", big("18446744073709551616")),
+        ("-(-9223372036854775807 - 1)", big("9223372036854775808")),
+        ("9223372036854775808 - 1", Some(Object::Int(i64::MAX))),
+        (
+            "18446744073709551616 / 4294967296",
+            Some(Object::Int(4294967296)),
+        ),
+        ("18446744073709551617 % 10", Some(Object::Int(7))),
+        ("9223372036854775808 > 1", Some(Object::Bool(true))),
+        (
+            "9223372036854775808 == 9223372036854775807 + 1",
+            Some(Object::Bool(true)),
+        ),
+        (
+            "9223372036854775808 * 0.5",
+            Some(Object::Float(4611686018427387904.0)),
+        ),
+        (
+            r#"
+            let factorial = fn(x) {
+                if (x > 1) { x * factorial(x - 1) } else { 1 }
+            };
+            factorial(50)
+            "#,
+            big("30414093201713378043612608166064768844377641568960512000000000000"),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_float_infix_eval() {
+    let tests = vec![
+        ("2.5", Some(Object::Float(2.5))),
+        ("-2.5", Some(Object::Float(-2.5))),
+        ("1.5 + 1.5", Some(Object::Float(3.0))),
+        ("1 + 0.5", Some(Object::Float(1.5))),
+        ("0.5 * 4", Some(Object::Float(2.0))),
+        ("7 / 2", Some(Object::Int(3))),
+        ("7 / 2.0", Some(Object::Float(3.5))),
+        ("7.0 / 2", Some(Object::Float(3.5))),
+        ("5.5 % 2", Some(Object::Float(1.5))),
+        ("1e3 - 1", Some(Object::Float(999.0))),
+        ("0.1 < 0.2", Some(Object::Bool(true))),
+        ("2 >= 2.5", Some(Object::Bool(false))),
+        ("1 == 1.0", Some(Object::Bool(true))),
+        ("1.5 != 1.5", Some(Object::Bool(false))),
+        (
+            "1.5 + true",
+            Some(Object::Error(String::from(
+                "type mismatch: 1.5 + true at line 1, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_checked_arithmetic_eval() {
+    let tests = vec![
+        (
+            "1 / 0",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 1",
+            ))),
+        ),
+        (
+            "5 % 0",
+            Some(Object::Error(String::from(
+                "modulo by zero at line 1, column 1",
+            ))),
+        ),
+        (
+            "let x = 0;\nlet y = 10 / x;\ny",
+            Some(Object::Error(String::from(
+                "division by zero at line 2, column 9",
+            ))),
+        ),
+        (
+            "1.5 / 0",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 1",
+            ))),
+        ),
+        (
+            "2.5 % 0.0",
+            Some(Object::Error(String::from(
+                "modulo by zero at line 1, column 1",
+            ))),
+        ),
+        (
+            "99999999999999999999 / 0",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 1",
+            ))),
+        ),
+        (
+            "let f = fn(x) { 100 / x };\nf(0) + 1",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 17",
+            ))),
+        ),
+        (
+            "(-9223372036854775807 - 1) / -1",
+            Some(Object::BigInt("9223372036854775808".parse().unwrap())),
+        ),
+        ("(-9223372036854775807 - 1) % -1", Some(Object::Int(0))),
+        ("0 / 5", Some(Object::Int(0))),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_bitwise_eval() {
+    let tests = vec![
+        ("12 & 10", Some(Object::Int(8))),
+        ("12 | 10", Some(Object::Int(14))),
+        ("12 ^ 10", Some(Object::Int(6))),
+        ("~0", Some(Object::Int(-1))),
+        ("1 << 10", Some(Object::Int(1024))),
+        ("-16 >> 2", Some(Object::Int(-4))),
+        ("1 >> 64", Some(Object::Int(0))),
+        ("0xFF & 0x0F == 0x0F", Some(Object::Bool(true))),
+        (
+            "1 << 64",
+            Some(Object::BigInt("18446744073709551616".parse().unwrap())),
+        ),
+        ("(1 << 64) >> 60", Some(Object::Int(16))),
+        ("(1 << 64 | 5) & 7", Some(Object::Int(5))),
+        (
+            "~(1 << 64)",
+            Some(Object::BigInt("-18446744073709551617".parse().unwrap())),
+        ),
+        (
+            "1 << -1",
+            Some(Object::Error(String::from(
+                "negative shift amount: -1 at line 1, column 1",
+            ))),
+        ),
+        (
+            "1 << 10000000",
+            Some(Object::Error(String::from(
+                "integer overflow: 1 << 10000000 at line 1, column 1",
+            ))),
+        ),
+        (
+            "1.5 & 1",
+            Some(Object::Error(String::from(
+                "unknown operator: 1.5 & 1 at line 1, column 1",
+            ))),
+        ),
+        (
+            "~true",
+            Some(Object::Error(String::from(
+                "unknown operator: ~true at line 1, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_power_eval() {
+    let tests = vec![
+        ("2 ** 10", Some(Object::Int(1024))),
+        ("2 ** 3 ** 2", Some(Object::Int(512))),
+        ("-2 ** 2", Some(Object::Int(-4))),
+        ("(-2) ** 3", Some(Object::Int(-8))),
+        ("7 ** 0", Some(Object::Int(1))),
+        (
+            "2 ** 100",
+            Some(Object::BigInt(
+                "1267650600228229401496703205376".parse().unwrap(),
+            )),
+        ),
+        ("4 ** 0.5", Some(Object::Float(2.0))),
+        ("2.0 ** -1", Some(Object::Float(0.5))),
+        ("1 ** 100000000", Some(Object::Int(1))),
+        (
+            "2 ** -1",
+            Some(Object::Error(String::from(
+                "negative exponent: -1 at line 1, column 1",
+            ))),
+        ),
+        (
+            "10 ** 10000000",
+            Some(Object::Error(String::from(
+                "integer overflow: 10 ** 10000000 at line 1, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
 #[test]
 fn test_if_eval() {
     let tests = vec![
@@ -119,6 +354,40 @@ fn test_if_eval() {
     test!(tests);
 }
 
+#[test]
+fn test_logical_eval() {
+    let tests = vec![
+        ("true && true", Some(Object::Bool(true))),
+        ("true && false", Some(Object::Bool(false))),
+        ("false || true", Some(Object::Bool(true))),
+        ("false || false", Some(Object::Bool(false))),
+        ("1 < 2 && 2 < 3", Some(Object::Bool(true))),
+        ("1 > 2 || 3 > 2 && 0 > 1", Some(Object::Bool(false))),
+        ("1 && \"a\"", Some(Object::Bool(true))),
+        (
+            "if (5 > 0 && 5 < 10) { 1 } else { 2 }",
+            Some(Object::Int(1)),
+        ),
+        // the right-hand side is only evaluated when needed
+        ("false && 1 / 0", Some(Object::Bool(false))),
+        ("true || missing()", Some(Object::Bool(true))),
+        (
+            "true && 1 / 0",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 9",
+            ))),
+        ),
+        (
+            "false || missing",
+            Some(Object::Error(String::from(
+                "identifier not found: missing at line 1, column 10",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
 #[test]
 fn test_return_eval() {
     let tests = vec![
@@ -146,32 +415,38 @@ fn test_error_handling() {
     let tests = vec![
         (
             "5 + true",
-            Some(Object::Error(String::from("type mismatch: 5 + true"))),
+            Some(Object::Error(String::from(
+                "type mismatch: 5 + true at line 1, column 1",
+            ))),
         ),
         (
             "5 + true; 5; ",
-            Some(Object::Error(String::from("type mismatch: 5 + true"))),
+            Some(Object::Error(String::from(
+                "type mismatch: 5 + true at line 1, column 1",
+            ))),
         ),
         (
             "-true",
-            Some(Object::Error(String::from("unknown operator: -true"))),
+            Some(Object::Error(String::from(
+                "unknown operator: -true at line 1, column 1",
+            ))),
         ),
         (
             "true + false",
             Some(Object::Error(String::from(
-                "unknown operator: true + false",
+                "unknown operator: true + false at line 1, column 1",
             ))),
         ),
         (
             "5; true + false; 5",
             Some(Object::Error(String::from(
-                "unknown operator: true + false",
+                "unknown operator: true + false at line 1, column 4",
             ))),
         ),
         (
             "if (10 > 1) { true + false; }",
             Some(Object::Error(String::from(
-                "unknown operator: true + false",
+                "unknown operator: true + false at line 1, column 15",
             ))),
         ),
         (
@@ -182,35 +457,73 @@ fn test_error_handling() {
                 return 1;
              }",
             Some(Object::Error(String::from(
-                "unknown operator: true + false",
+                "unknown operator: true + false at line 3, column 28",
             ))),
         ),
         (
             "1+(true+false)",
             Some(Object::Error(String::from(
-                "unknown operator: true + false",
+                "unknown operator: true + false at line 1, column 3",
             ))),
         ),
         (
             "(true+false)+1",
             Some(Object::Error(String::from(
-                "unknown operator: true + false",
+                "unknown operator: true + false at line 1, column 1",
             ))),
         ),
         (
             "(true+false)+(true+false)",
             Some(Object::Error(String::from(
-                "unknown operator: true + false",
+                "unknown operator: true + false at line 1, column 1",
             ))),
         ),
         (
             "foobar",
-            Some(Object::Error(String::from("identifier not found: foobar"))),
+            Some(Object::Error(String::from(
+                "identifier not found: foobar at line 1, column 1",
+            ))),
         ),
         (
             "{true: \"owo\"}[fn(x) {x}];",
             Some(Object::Error(String::from(
-                "unsable as hash key: fn(x) { ... }",
+                "unsable as hash key: fn(x) { ... } at line 1, column 1",
+            ))),
+        ),
+        (
+            "let f = fn(x) { x }; f(1/0)",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 24",
+            ))),
+        ),
+        (
+            "print(1/0); print(\"after\");",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 7",
+            ))),
+        ),
+        (
+            "let f = fn(x = 0) { x }; f(x: 1/0)",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 31",
+            ))),
+        ),
+        (
+            "let a = [1, 1/0]; print(\"after\")",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 13",
+            ))),
+        ),
+        (
+            "{1/0: 1}",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 2",
+            ))),
+        ),
+        (
+            "{1: 1/0}",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 5",
             ))),
         ),
     ];
@@ -233,19 +546,166 @@ fn test_let_statements() {
     test!(tests);
 }
 
+#[test]
+fn test_update_statements() {
+    let tests =
+        vec![
+        ("let a = 1; update a = 5; a", Some(Object::Int(5))),
+        ("let a = 1; update a += 2; a", Some(Object::Int(3))),
+        ("let a = 10; update a -= 4; update a *= 3; a", Some(Object::Int(18))),
+        ("let a = 10; update a /= 4; a", Some(Object::Int(2))),
+        ("let a = 10; update a %= 4; a", Some(Object::Int(2))),
+        ("let s = \"a\"; update s += \"b\"; s", Some(Object::String(String::from("ab")))),
+        ("let a = [1, 2, 3]; update a[1] = 5; a[1]", Some(Object::Int(5))),
+        ("let a = [1, 2, 3]; update a[-1] += 5; a[2]", Some(Object::Int(8))),
+        (
+            "let grid = [[0, 0], [0, 0]]; update grid[1][0] = 7; grid",
+            Some(Object::Array(vec![
+                Object::Array(vec![Object::Int(0), Object::Int(0)]),
+                Object::Array(vec![Object::Int(7), Object::Int(0)]),
+            ])),
+        ),
+        ("let h = {\"k\": 1}; update h[\"k\"] += 1; h[\"k\"]", Some(Object::Int(2))),
+        ("let h = {}; update h[\"new\"] = 3; h[\"new\"]", Some(Object::Int(3))),
+        (
+            "let h = {\"a\": {\"b\": [1, 2]}}; update h[\"a\"][\"b\"][0] = 9; h[\"a\"][\"b\"][0]",
+            Some(Object::Int(9)),
+        ),
+        (
+            "let xs = [0]; let bump = fn() { update xs[0] += 1; }; bump(); bump(); xs[0]",
+            Some(Object::Int(2)),
+        ),
+        (
+            "let a = [1]; update a[1] = 2;",
+            Some(Object::Error(String::from(
+                "index out of bounds: 1 at line 1, column 21",
+            ))),
+        ),
+        (
+            "let h = {}; update h[\"x\"] += 1;",
+            Some(Object::Error(String::from(
+                "key not found: x at line 1, column 20",
+            ))),
+        ),
+        (
+            "let a = 1; update a[0] = 2;",
+            Some(Object::Error(String::from(
+                "index operator not supported: 1 at line 1, column 19",
+            ))),
+        ),
+        (
+            "let a = true; update a += 1;",
+            Some(Object::Error(String::from(
+                "unknown operator: true + 1 at line 1, column 22",
+            ))),
+        ),
+        (
+            "update missing += 1;",
+            Some(Object::Error(String::from(
+                "identifier not found: missing at line 1, column 8",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_loop_statements() {
+    let tests = vec![
+        (
+            "let i = 0; let s = 0; while (i < 5) { update s += i; update i += 1; } s",
+            Some(Object::Int(10)),
+        ),
+        (
+            "let s = 0; for (x in [1, 2, 3]) { update s += x; } s",
+            Some(Object::Int(6)),
+        ),
+        (
+            "let s = 0; for (k in {1: \"a\", 2: \"b\", 3: \"c\"}) { update s += k; } s",
+            Some(Object::Int(6)),
+        ),
+        (
+            "let s = \"\"; for (c in \"héllo\") { update s = c + s; } s",
+            Some(Object::String(String::from("olléh"))),
+        ),
+        (
+            "let s = 0; for (x in [1, 2, 3, 4, 5]) { if (x == 2) { continue; } if (x == 4) { break; } update s += x; } s",
+            Some(Object::Int(4)),
+        ),
+        (
+            "let i = 0; while (true) { update i += 1; if (i == 3) { break; } } i",
+            Some(Object::Int(3)),
+        ),
+        (
+            "let s = 0; for (row in [[1, 2], [3, 4]]) { for (x in row) { if (x == 2) { break; } update s += x; } } s",
+            Some(Object::Int(8)),
+        ),
+        (
+            "let find = fn(xs) { for (x in xs) { if (x > 1) { return x; } } -1 }; find([1, 5, 7])",
+            Some(Object::Int(5)),
+        ),
+        // jumps out of blocks nested in expressions stop the statement
+        (
+            "let i = 0; while (i < 3) { let x = if (i == 1) { break; }; update i += 1; } i",
+            Some(Object::Int(1)),
+        ),
+        (
+            "let s = 0; for (x in [1, 2, 3]) { update s += if (x == 2) { continue; } else { x }; } s",
+            Some(Object::Int(4)),
+        ),
+        (
+            "let s = []; for (x in [1, 2, 3]) { update s = push(s, if (x == 3) { break; } else { x }); } s",
+            Some(Object::Array(vec![Object::Int(1), Object::Int(2)])),
+        ),
+        (
+            "let f = fn(x) { let y = [if (x > 0) { return 1; }]; 0 }; f(5)",
+            Some(Object::Int(1)),
+        ),
+        // loops run in constant stack
+        (
+            "let i = 0; while (i < 100000) { update i += 1; } i",
+            Some(Object::Int(100000)),
+        ),
+        (
+            "for (x in 5) {}",
+            Some(Object::Error(String::from(
+                "cannot iterate over 5 at line 1, column 11",
+            ))),
+        ),
+        (
+            "while (1 / 0) {}",
+            Some(Object::Error(String::from(
+                "division by zero at line 1, column 8",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
 #[test]
 fn test_fn_object() {
     let tests = vec![(
         "fn (x) {x+2}",
-        Some(Object::Fn(
-            vec![Ident(String::from("x"))],
-            vec![Statement::Expression(Expression::Infix(
-                Infix::Plus,
-                Box::new(Expression::Ident(Ident(String::from("x")))),
-                Box::new(Expression::Literal(Literal::Int(2))),
-            ))],
-            Rc::new(RefCell::new(Env::new())),
-        )),
+        Some(Object::Fn(Rc::new(Function {
+            name: None,
+            params: vec![Param {
+                name: Ident(String::from("x")),
+                default: None,
+            }],
+            rest: None,
+            body: vec![Statement::Expression(
+                Expression::Infix(
+                    Infix::Plus,
+                    Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                    Box::new(Expression::Literal(Literal::Int(2)).into()),
+                )
+                .into(),
+            )
+            .into()],
+            env: Rc::new(RefCell::new(Env::new())),
+        }))),
     )];
 
     test!(tests);
@@ -293,19 +753,31 @@ fn test_builtin_functions() {
         (
             "len(1)",
             Some(Object::Error(
-                "argument to `len` not supported, got: 1".to_string(),
+                "argument to `len` not supported, got: 1 at line 1, column 1".to_string(),
             )),
         ),
         (
             "len(\"one\", \"two\")",
             Some(Object::Error(String::from(
-                "expected arguments: 1\ngiven arguments: 2",
+                "expected arguments: 1\ngiven arguments: 2 at line 1, column 1",
             ))),
         ),
     ];
     test!(tests);
 }
 
+#[test]
+fn test_std_functions() {
+    let tests = vec![
+        ("import std; abs(-2)", Some(Object::Int(2))),
+        ("import std; abs(3)", Some(Object::Int(3))),
+        ("import std; abs(0)", Some(Object::Int(0))),
+        ("import std; first([1, 2])", Some(Object::Int(1))),
+        ("import std; last([1, 2])", Some(Object::Int(2))),
+    ];
+    test!(tests);
+}
+
 #[test]
 fn test_array_eval() {
     let tests = vec![(
@@ -347,6 +819,7 @@ fn test_array_index_eval() {
 }
 
 #[test]
+#[allow(clippy::mutable_key_type)]
 fn test_hash_literal() {
     let mut hash = HashMap::new();
     hash.insert(Object::String(String::from("one")), Object::Int(1));
@@ -384,3 +857,498 @@ fn test_hash_index_expression() {
     ];
     test!(tests);
 }
+
+#[test]
+fn test_match_expression() {
+    let describe = r#"
+        let describe = fn(value) {
+            match (value) {
+                0 => "zero",
+                -1 => "minus one",
+                "hi" => "greeting",
+                true => "yes",
+                [] => "empty",
+                [x] => x,
+                [first, ...rest] => rest,
+                {"type": "circle", "r": r} => r,
+                {"type": t} => t,
+                n if n > 10 => { let doubled = n * 2; doubled }
+                _ => "other",
+            }
+        };
+    "#;
+    let tests = vec![
+        ("describe(0)", Some(Object::String(String::from("zero")))),
+        ("describe(0.0)", Some(Object::String(String::from("zero")))),
+        (
+            "describe(-1)",
+            Some(Object::String(String::from("minus one"))),
+        ),
+        (
+            "describe(\"hi\")",
+            Some(Object::String(String::from("greeting"))),
+        ),
+        ("describe(true)", Some(Object::String(String::from("yes")))),
+        ("describe([])", Some(Object::String(String::from("empty")))),
+        ("describe([5])", Some(Object::Int(5))),
+        (
+            "describe([1, 2, 3])",
+            Some(Object::Array(vec![Object::Int(2), Object::Int(3)])),
+        ),
+        (
+            "describe({\"type\": \"circle\", \"r\": 2})",
+            Some(Object::Int(2)),
+        ),
+        (
+            "describe({\"type\": \"square\"})",
+            Some(Object::String(String::from("square"))),
+        ),
+        ("describe(11)", Some(Object::Int(22))),
+        ("describe(3)", Some(Object::String(String::from("other")))),
+        ("describe(2.5)", Some(Object::String(String::from("other")))),
+    ];
+    let tests = tests
+        .into_iter()
+        .map(|(input, expect)| (format!("{} {}", describe, input), expect));
+    test!(tests);
+
+    let tests = vec![
+        ("match (1) { 2 => 3 }", None),
+        ("match ([1, 2]) { [a, b] => a + b }", Some(Object::Int(3))),
+        ("match ([1, 2]) { [a] => a, _ => 0 }", Some(Object::Int(0))),
+        (
+            "let f = fn(x) { match (x) { _ => { return 5; } }; 6 }; f(1)",
+            Some(Object::Int(5)),
+        ),
+        (
+            "match (1) { x if y => x }",
+            Some(Object::Error(String::from(
+                "identifier not found: y at line 1, column 18",
+            ))),
+        ),
+        // pattern bindings only live in their arm
+        (
+            "let x = 1; match (5) { x if x > 10 => 0, _ => x }",
+            Some(Object::Int(1)),
+        ),
+        (
+            "let x = 1; match (5) { x if x > 10 => 0, _ => 1 }; x",
+            Some(Object::Int(1)),
+        ),
+        ("let x = 1; match (5) { x => x }; x", Some(Object::Int(1))),
+        (
+            "match (5) { y => y }; y",
+            Some(Object::Error(String::from(
+                "identifier not found: y at line 1, column 23",
+            ))),
+        ),
+        (
+            "let s = 0; match (2) { n => { update s += n; } }; s",
+            Some(Object::Int(2)),
+        ),
+        // a `{` body is a block, so a hash literal goes in parentheses
+        (
+            "match (0) { 0 => ({\"a\": 1}), _ => { {\"a\": 2} } }[\"a\"]",
+            Some(Object::Int(1)),
+        ),
+        (
+            "match (1) { 0 => ({\"a\": 1}), _ => { {\"a\": 2} } }[\"a\"]",
+            Some(Object::Int(2)),
+        ),
+    ];
+    test!(tests);
+}
+
+#[test]
+fn test_destructuring_let() {
+    let tests = vec![
+        (
+            "let [a, b, ...rest] = [1, 2, 3, 4]; [a, b, rest]",
+            Some(Object::Array(vec![
+                Object::Int(1),
+                Object::Int(2),
+                Object::Array(vec![Object::Int(3), Object::Int(4)]),
+            ])),
+        ),
+        ("let [a, ...rest] = [1]; rest", Some(Object::Array(vec![]))),
+        (
+            "let {\"name\": n, \"age\": a} = {\"name\": \"Ada\", \"age\": 36}; n",
+            Some(Object::String(String::from("Ada"))),
+        ),
+        (
+            "let {name, age} = {\"name\": \"Ada\", \"age\": 36}; age",
+            Some(Object::Int(36)),
+        ),
+        (
+            "let [x, [y, z]] = [1, [2, 3]]; x + y + z",
+            Some(Object::Int(6)),
+        ),
+        ("let [_, second] = [1, 2]; second", Some(Object::Int(2))),
+        (
+            "let [a, b] = [1];",
+            Some(Object::Error(String::from(
+                "expected an array of length 2, got [1] at line 1, column 1",
+            ))),
+        ),
+        (
+            "let [a, b, ...rest] = [1];",
+            Some(Object::Error(String::from(
+                "expected an array of length at least 2, got [1] at line 1, column 1",
+            ))),
+        ),
+        (
+            "let [a] = 5;",
+            Some(Object::Error(String::from(
+                "cannot destructure 5 as an array at line 1, column 1",
+            ))),
+        ),
+        (
+            "let {name} = {\"age\": 1};",
+            Some(Object::Error(String::from(
+                "key not found: name at line 1, column 1",
+            ))),
+        ),
+        (
+            "let {name} = [1];",
+            Some(Object::Error(String::from(
+                "cannot destructure [1] as a hash at line 1, column 1",
+            ))),
+        ),
+        (
+            "let x = 1;\nlet [0, y] = [x, 2];",
+            Some(Object::Error(String::from(
+                "expected 0, got 1 at line 2, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_fn_parameters() {
+    let tests = vec![
+        (
+            "let f = fn(x, y = 10) { x + y }; [f(1), f(1, 2)]",
+            Some(Object::Array(vec![Object::Int(11), Object::Int(3)])),
+        ),
+        ("let f = fn(x, y = x * 2) { y }; f(4)", Some(Object::Int(8))),
+        (
+            "let f = fn(x, ...rest) { rest }; [f(1), f(1, 2, 3)]",
+            Some(Object::Array(vec![
+                Object::Array(vec![]),
+                Object::Array(vec![Object::Int(2), Object::Int(3)]),
+            ])),
+        ),
+        (
+            "let f = fn(a, b = 2, c = 3) { [a, b, c] }; f(1, c: 30)",
+            Some(Object::Array(vec![
+                Object::Int(1),
+                Object::Int(2),
+                Object::Int(30),
+            ])),
+        ),
+        (
+            "let f = fn(a, b) { a - b }; f(b: 1, a: 5)",
+            Some(Object::Int(4)),
+        ),
+        (
+            "let f = fn(x) { x }; f(1, 2)",
+            Some(Object::Error(String::from(
+                "too many arguments for `f`: expected at most 1, got 2 at line 1, column 22",
+            ))),
+        ),
+        (
+            "let f = fn(x) { x }; f(1, x: 2)",
+            Some(Object::Error(String::from(
+                "multiple values for parameter `x` of `f` at line 1, column 22",
+            ))),
+        ),
+        (
+            "let f = fn(x) { x }; f(y: 2)",
+            Some(Object::Error(String::from(
+                "unknown parameter `y` for `f` at line 1, column 22",
+            ))),
+        ),
+        (
+            "fn(x, y) { x }(1)",
+            Some(Object::Error(String::from(
+                "missing argument for parameter `y` of anonymous function at line 1, column 1",
+            ))),
+        ),
+        (
+            "len(x: [1])",
+            Some(Object::Error(String::from(
+                "`len` does not take keyword arguments at line 1, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_fn_statement() {
+    let tests = vec![
+        ("fn double(x) { x * 2 } double(4)", Some(Object::Int(8))),
+        (
+            "let a = double(2); fn double(x) { x * 2 } a",
+            Some(Object::Int(4)),
+        ),
+        (
+            "is_even(10); fn is_even(n) { if (n == 0) { true } else { is_odd(n - 1) } } \
+             fn is_odd(n) { if (n == 0) { false } else { is_even(n - 1) } } is_odd(7)",
+            Some(Object::Bool(true)),
+        ),
+        (
+            "let f = fn() { let x = g(); fn g() { 5 } x }; f()",
+            Some(Object::Int(5)),
+        ),
+        (
+            "fn add(a, b = 1) { a + b } \"${add}\"",
+            Some(Object::String(String::from("fn add(a, b = 1) { ... }"))),
+        ),
+        (
+            "fn add(a, b) { a + b } let plus = add; plus(1, 2, 3)",
+            Some(Object::Error(String::from(
+                "too many arguments for `add`: expected at most 2, got 3 at line 1, column 40",
+            ))),
+        ),
+        // hoisted functions see libraries imported after the block starts
+        (
+            "import std; fn f(xs) { last(xs) } f([1, 2])",
+            Some(Object::Int(2)),
+        ),
+        (
+            "fn f(xs) { first(xs) } import std; f([1, 2])",
+            Some(Object::Int(1)),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_range() {
+    let ints = |items: &[i64]| {
+        Some(Object::Array(
+            items.iter().map(|i| Object::Int(*i)).collect(),
+        ))
+    };
+    let tests = vec![
+        ("to_array(0..4)", ints(&[0, 1, 2, 3])),
+        ("to_array(1..=4)", ints(&[1, 2, 3, 4])),
+        ("to_array(0..10 step 4)", ints(&[0, 4, 8])),
+        ("to_array(5..=1 step -2)", ints(&[5, 3, 1])),
+        ("to_array(3..0)", ints(&[])),
+        ("len(0..10 step 3)", Some(Object::Int(4))),
+        (
+            "len(-9223372036854775807..9223372036854775807)",
+            Some(Object::Int(i64::MAX)),
+        ),
+        ("(0..10 step 3)[1]", Some(Object::Int(3))),
+        ("(0..10 step 3)[-1]", Some(Object::Int(9))),
+        ("(0..3)[3]", Some(Object::Null)),
+        (
+            "let total = 0; for (i in 1..=100) { update total += i; } total",
+            Some(Object::Int(5050)),
+        ),
+        ("to_array(tail(0..3))", ints(&[1, 2])),
+        ("len(tail(0..1))", Some(Object::Int(0))),
+        (
+            "1..=3",
+            Some(Object::Range(Range {
+                start: 1,
+                end: 4,
+                step: 1,
+                inclusive: true,
+            })),
+        ),
+        ("\"${1..=3}\"", Some(Object::String(String::from("1..=3")))),
+        ("\"${1..4}\"", Some(Object::String(String::from("1..4")))),
+        (
+            "\"${10..=0 step -5}\"",
+            Some(Object::String(String::from("10..=0 step -5"))),
+        ),
+        (
+            "import std; \"${range(3)}\"",
+            Some(Object::String(String::from("1..=3"))),
+        ),
+        ("import std; push(range(3), 4)", ints(&[1, 2, 3, 4])),
+        ("push(0..0, 1)", ints(&[1])),
+        (
+            "\"${0..6 step 2}\"",
+            Some(Object::String(String::from("0..6 step 2"))),
+        ),
+        ("import std; range(3)[-1]", Some(Object::Int(3))),
+        (
+            "0..\"a\"",
+            Some(Object::Error(String::from(
+                "range bounds must be integers, got a at line 1, column 4",
+            ))),
+        ),
+        (
+            "0..3 step 0",
+            Some(Object::Error(String::from(
+                "range step cannot be zero at line 1, column 11",
+            ))),
+        ),
+        (
+            "0..=9223372036854775807",
+            Some(Object::Error(String::from(
+                "range end is too large: 9223372036854775807 at line 1, column 5",
+            ))),
+        ),
+        (
+            "to_array(5)",
+            Some(Object::Error(String::from(
+                "argument to `to_array` not supported, got: 5 at line 1, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_pipe() {
+    let tests =
+        vec![
+        ("let double = fn(x) { x * 2 }; 5 |> double", Some(Object::Int(10))),
+        ("let sub = fn(a, b) { a - b }; 10 |> sub(3)", Some(Object::Int(7))),
+        (
+            "let double = fn(x) { x * 2 }; let add = fn(a, b) { a + b }; 1 + 2 |> double |> add(1)",
+            Some(Object::Int(7)),
+        ),
+        ("0..5 |> to_array |> len", Some(Object::Int(5))),
+        (
+            "let f = fn(x, scale = 1) { x * scale }; 3 |> f(scale: 4)",
+            Some(Object::Int(12)),
+        ),
+        (
+            "let f = fn(x) { x }; 1 |> f(2)",
+            Some(Object::Error(String::from(
+                "too many arguments for `f`: expected at most 1, got 2 at line 1, column 27",
+            ))),
+        ),
+        (
+            "1 |> 2",
+            Some(Object::Error(String::from(
+                "function not found: 2 at line 1, column 6",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_null() {
+    let tests = vec![
+        ("null", Some(Object::Null)),
+        ("{\"a\": 1}[\"b\"] ?? 2", Some(Object::Int(2))),
+        ("0 ?? 2", Some(Object::Int(0))),
+        ("false ?? 2", Some(Object::Bool(false))),
+        ("null ?? null ?? 3", Some(Object::Int(3))),
+        ("1 ?? missing", Some(Object::Int(1))),
+        (
+            "let h = {\"a\": [1, 2]}; h[\"a\"]?[1]",
+            Some(Object::Int(2)),
+        ),
+        ("let h = {}; h[\"a\"]?[1]", Some(Object::Null)),
+        (
+            "let h = {}; h[\"a\"]?[1] ?? \"none\"",
+            Some(Object::String(String::from("none"))),
+        ),
+        ("let f = fn(x) { x * 2 }; f?.(4)", Some(Object::Int(8))),
+        ("let f = null; f?.(missing)", Some(Object::Null)),
+        // an optional step that finds null skips the rest of its chain
+        ("let a = null; a?[\"b\"][\"c\"]", Some(Object::Null)),
+        ("let a = null; a?[\"b\"][\"c\"](1)[0:1]", Some(Object::Null)),
+        ("let f = null; f?.(1)[0]", Some(Object::Null)),
+        (
+            "let a = null; (a?[\"b\"] ?? {\"c\": 1})[\"c\"]",
+            Some(Object::Int(1)),
+        ),
+        (
+            "let a = {\"b\": null}; a?[\"b\"][\"c\"]",
+            Some(Object::Error(String::from(
+                "unknown operator: null c at line 1, column 22",
+            ))),
+        ),
+        ("null == null", Some(Object::Bool(true))),
+        ("1 == null", Some(Object::Bool(false))),
+        ("[] != null", Some(Object::Bool(true))),
+        (
+            "match ({}[\"a\"]) { null => \"none\", _ => \"some\" }",
+            Some(Object::String(String::from("none"))),
+        ),
+        (
+            "let h = {}; h[\"a\"][1]",
+            Some(Object::Error(String::from(
+                "unknown operator: null 1 at line 1, column 13",
+            ))),
+        ),
+        (
+            "let f = null; f(1)",
+            Some(Object::Error(String::from(
+                "function not found: null at line 1, column 15",
+            ))),
+        ),
+        (
+            "null + 1",
+            Some(Object::Error(String::from(
+                "unknown operator: null + 1 at line 1, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
+
+#[test]
+fn test_slice() {
+    let ints = |items: &[i64]| {
+        Some(Object::Array(
+            items.iter().map(|i| Object::Int(*i)).collect(),
+        ))
+    };
+    let string = |s: &str| Some(Object::String(String::from(s)));
+    let tests = vec![
+        ("[0, 1, 2, 3, 4][1:3]", ints(&[1, 2])),
+        ("[0, 1, 2, 3, 4][:-2]", ints(&[0, 1, 2])),
+        ("[0, 1, 2, 3, 4][-2:]", ints(&[3, 4])),
+        ("[0, 1, 2, 3, 4][::2]", ints(&[0, 2, 4])),
+        ("[0, 1, 2, 3, 4][::-1]", ints(&[4, 3, 2, 1, 0])),
+        ("[0, 1, 2, 3, 4][3:0:-2]", ints(&[3, 1])),
+        ("[0, 1, 2, 3, 4][-100:100]", ints(&[0, 1, 2, 3, 4])),
+        ("[0, 1, 2, 3, 4][3:1]", ints(&[])),
+        ("\"héllo\"[1]", string("é")),
+        ("\"héllo\"[-1]", string("o")),
+        ("\"héllo\"[5]", Some(Object::Null)),
+        ("\"héllo\"[1:3]", string("él")),
+        ("\"héllo\"[::-1]", string("olléh")),
+        ("len(\"héllo\")", Some(Object::Int(5))),
+        ("let h = {}; h[\"a\"]?[1:]", Some(Object::Null)),
+        (
+            "[1, 2][::0]",
+            Some(Object::Error(String::from(
+                "slice step cannot be zero at line 1, column 1",
+            ))),
+        ),
+        (
+            "[1, 2][\"a\":]",
+            Some(Object::Error(String::from(
+                "slice bounds must be integers, got a at line 1, column 8",
+            ))),
+        ),
+        (
+            "5[1:]",
+            Some(Object::Error(String::from(
+                "slice operator not supported: 5 at line 1, column 1",
+            ))),
+        ),
+    ];
+
+    test!(tests);
+}
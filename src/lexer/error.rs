@@ -0,0 +1,47 @@
+use crate::token::Span;
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    IllegalCharacter(char, Span),
+    UnterminatedString(Span),
+    UnterminatedComment(Span),
+    InvalidNumber(String, Span),
+    InvalidEscape(String, Span),
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match *self {
+            LexError::IllegalCharacter(_, span)
+            | LexError::UnterminatedString(span)
+            | LexError::UnterminatedComment(span)
+            | LexError::InvalidNumber(_, span)
+            | LexError::InvalidEscape(_, span) => span,
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::IllegalCharacter(ch, span) => {
+                write!(f, "Illegal character `{}` at {}", ch, span)
+            }
+            LexError::UnterminatedString(span) => {
+                write!(f, "Unterminated string starting at {}", span)
+            }
+            LexError::UnterminatedComment(span) => {
+                write!(f, "Unterminated block comment starting at {}", span)
+            }
+            LexError::InvalidNumber(literal, span) => {
+                write!(f, "Invalid number literal `{}` at {}", literal, span)
+            }
+            LexError::InvalidEscape(escape, span) => {
+                write!(f, "Invalid escape sequence `{}` at {}", escape, span)
+            }
+        }
+    }
+}
+
+impl Error for LexError {}
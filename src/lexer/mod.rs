@@ -1,7 +1,11 @@
+pub mod error;
+
 #[cfg(test)]
 pub mod test;
 
-use crate::token::Token;
+use crate::token::{Comment, Position, Span, SpannedToken, StringPart, Token};
+use error::LexError;
+use num_bigint::BigInt;
 use std::collections::HashMap;
 
 lazy_static::lazy_static! {
@@ -16,6 +20,13 @@ lazy_static::lazy_static! {
         keywords.insert("else", Token::Else);
         keywords.insert("return", Token::Return);
         keywords.insert("import", Token::Import);
+        keywords.insert("while", Token::While);
+        keywords.insert("for", Token::For);
+        keywords.insert("in", Token::In);
+        keywords.insert("break", Token::Break);
+        keywords.insert("continue", Token::Continue);
+        keywords.insert("match", Token::Match);
+        keywords.insert("null", Token::Null);
         keywords
     };
 }
@@ -24,45 +35,121 @@ pub fn lookup_indentifier(i: &str) -> Option<&Token> {
     KEYWORDS.get(i)
 }
 
+/// Walks the input one character at a time. `position` and `read_position`
+/// are byte offsets into `input` of `ch` and the character after it, so
+/// reading a character is constant time and slicing by them is always on a
+/// UTF-8 boundary.
+///
+/// The lexer is an iterator over the tokens of its input, ending after the
+/// `Eof` token.
 pub struct Lexer {
     pub input: String,
     pub position: usize,
     pub read_position: usize,
     pub ch: char,
+    pub line: usize,
+    pub column: usize,
+    pub start_offset: usize,
+    pub token_start: Position,
+    pub finished: bool,
+    /// Every comment skipped so far, in source order.
+    pub comments: Vec<Comment>,
 }
 
 impl Lexer {
     pub fn new(input: String) -> Lexer {
+        let ch = input.chars().next().unwrap_or(0 as char);
         Lexer {
-            ch: input.chars().next().unwrap(),
+            read_position: if input.is_empty() { 0 } else { ch.len_utf8() },
+            ch,
             input,
             position: 0,
-            read_position: 1,
+            line: 1,
+            column: 1,
+            start_offset: 0,
+            token_start: Position::default(),
+            finished: false,
+            comments: vec![],
         }
     }
 
+    /// Creates a lexer whose positions start at `start` rather than the
+    /// beginning of a file, used for the code embedded in interpolated strings.
+    pub fn new_at(input: String, start: Position) -> Lexer {
+        let mut lexer = Lexer::new(input);
+        lexer.start_offset = start.offset;
+        lexer.line = start.line;
+        lexer.column = start.column;
+        lexer
+    }
+
     fn read_char(&mut self) {
-        self.ch = if self.read_position >= self.input.len() {
-            0 as char
+        if self.position >= self.input.len() {
+            return;
+        }
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            self.input.chars().nth(self.read_position).unwrap()
-        };
+            self.column += 1;
+        }
+
         self.position = self.read_position;
-        self.read_position += 1;
+        self.ch = self.char_at(self.position);
+        if self.position < self.input.len() {
+            self.read_position += self.ch.len_utf8();
+        }
     }
 
-    pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+    fn char_at(&self, position: usize) -> char {
+        self.input
+            .get(position..)
+            .and_then(|rest| rest.chars().next())
+            .unwrap_or(0 as char)
+    }
 
+    fn current_position(&self) -> Position {
+        Position::new(self.start_offset + self.position, self.line, self.column)
+    }
+
+    pub fn next_token(&mut self) -> Result<SpannedToken, LexError> {
+        self.skip_whitespace()?;
+
+        self.token_start = self.current_position();
+        let token = self.read_token()?;
+        Ok(SpannedToken {
+            token,
+            span: self.token_span(),
+        })
+    }
+
+    /// The span from the start of the token being read to the current character.
+    fn token_span(&self) -> Span {
+        Span::new(self.token_start, self.current_position())
+    }
+
+    /// The source text from the start of the token being read to the current character.
+    fn token_text(&self) -> String {
+        self.input[self.token_start.offset - self.start_offset..self.position].to_string()
+    }
+
+    fn invalid_number(&self) -> LexError {
+        LexError::InvalidNumber(self.token_text(), self.token_span())
+    }
+
+    fn read_token(&mut self) -> Result<Token, LexError> {
         let tok: Token = match self.ch {
-            '=' => {
-                if self.peek_char() == '=' {
+            '=' => match self.peek_char() {
+                '=' => {
                     self.read_char();
                     Token::Equal
-                } else {
-                    Token::Assign
                 }
-            }
+                '>' => {
+                    self.read_char();
+                    Token::FatArrow
+                }
+                _ => Token::Assign,
+            },
             ';' => Token::SemiColon,
             ':' => Token::Colon,
             '(' => Token::LParen,
@@ -72,9 +159,23 @@ impl Lexer {
             '[' => Token::LBracket,
             ']' => Token::RBracket,
             ',' => Token::Comma,
-            '+' => Token::Plus,
-            '-' => Token::Minus,
-            '%' => Token::Percent,
+            '.' if self.peek_char() == '.' => {
+                self.read_char();
+                match self.peek_char() {
+                    '.' => {
+                        self.read_char();
+                        Token::Ellipsis
+                    }
+                    '=' => {
+                        self.read_char();
+                        Token::DotDotEq
+                    }
+                    _ => Token::DotDot,
+                }
+            }
+            '+' => self.with_assign(Token::Plus, Token::PlusAssign),
+            '-' => self.with_assign(Token::Minus, Token::MinusAssign),
+            '%' => self.with_assign(Token::Percent, Token::PercentAssign),
             '!' => {
                 if self.peek_char() == '=' {
                     self.read_char();
@@ -83,44 +184,98 @@ impl Lexer {
                     Token::Bang
                 }
             }
-            '*' => Token::Asterisk,
-            '/' => Token::Slash,
-            '<' => {
-                if self.peek_char() == '=' {
+            '*' => {
+                if self.peek_char() == '*' {
                     self.read_char();
-                    Token::LtEq
+                    Token::Power
                 } else {
-                    Token::Lt
+                    self.with_assign(Token::Asterisk, Token::AsteriskAssign)
                 }
             }
-            '>' => {
-                if self.peek_char() == '=' {
+            '/' => self.with_assign(Token::Slash, Token::SlashAssign),
+            '<' => match self.peek_char() {
+                '=' => {
+                    self.read_char();
+                    Token::LtEq
+                }
+                '<' => {
+                    self.read_char();
+                    Token::ShiftLeft
+                }
+                _ => Token::Lt,
+            },
+            '>' => match self.peek_char() {
+                '=' => {
                     self.read_char();
                     Token::GtEq
+                }
+                '>' => {
+                    self.read_char();
+                    Token::ShiftRight
+                }
+                _ => Token::Gt,
+            },
+            '&' => {
+                if self.peek_char() == '&' {
+                    self.read_char();
+                    Token::And
                 } else {
-                    Token::Gt
+                    Token::Ampersand
                 }
             }
-            '"' => Token::Str(self.read_string()),
+            '|' => match self.peek_char() {
+                '|' => {
+                    self.read_char();
+                    Token::Or
+                }
+                '>' => {
+                    self.read_char();
+                    Token::Pipe
+                }
+                _ => Token::Bar,
+            },
+            '?' if matches!(self.peek_char(), '?' | '[' | '.') => {
+                self.read_char();
+                match self.ch {
+                    '?' => Token::Coalesce,
+                    '[' => Token::OptionalLBracket,
+                    _ => Token::OptionalDot,
+                }
+            }
+            '^' => Token::Caret,
+            '~' => Token::Tilde,
+            '"' => self.read_string()?,
             '\u{0}' => Token::Eof,
             _ => {
                 if is_letter(self.ch) {
                     let i: String = self.read_identifier();
-                    return match lookup_indentifier(i.as_str()) {
+                    return Ok(match lookup_indentifier(i.as_str()) {
                         Some(a) => a.to_owned(),
                         _ => Token::Ident(i),
-                    };
-                } else if self.ch.is_numeric() {
-                    let i: i32 = self.read_number();
-                    return Token::Int(i);
+                    });
+                } else if self.ch.is_ascii_digit() {
+                    return self.read_number();
                 } else {
-                    Token::Illegal
+                    let ch = self.ch;
+                    self.read_char();
+                    return Err(LexError::IllegalCharacter(ch, self.token_span()));
                 }
             }
         };
 
         self.read_char();
-        tok
+        Ok(tok)
+    }
+
+    /// Returns `compound` if the current character is followed by `=`,
+    /// consuming it, and `token` otherwise.
+    fn with_assign(&mut self, token: Token, compound: Token) -> Token {
+        if self.peek_char() == '=' {
+            self.read_char();
+            compound
+        } else {
+            token
+        }
     }
 
     fn read_identifier(&mut self) -> String {
@@ -131,36 +286,312 @@ impl Lexer {
         self.input[pos..self.position].to_string()
     }
 
-    fn read_number(&mut self) -> i32 {
-        let pos: usize = self.position;
-        while self.ch.is_numeric() {
+    /// Reads an integer or a float. A number is a float if it has a
+    /// fractional part (`3.14`) or an exponent (`1e-9`, `2.5E3`). Integers
+    /// may also be written in hex (`0xFF`), binary (`0b1010`) or octal
+    /// (`0o755`), and any digits may be separated by underscores (`1_000`).
+    /// Integers too large for an `i64` become big integers.
+    fn read_number(&mut self) -> Result<Token, LexError> {
+        if self.ch == '0' {
+            let radix = match self.peek_char() {
+                'x' => Some(16),
+                'b' => Some(2),
+                'o' => Some(8),
+                _ => None,
+            };
+            if let Some(radix) = radix {
+                self.read_char();
+                self.read_char();
+                let mut digits = String::new();
+                let valid = self.read_digits(radix, &mut digits);
+                if !self.end_of_number() || !valid {
+                    return Err(self.invalid_number());
+                }
+                return match i64::from_str_radix(&digits, radix) {
+                    Ok(i) => Ok(Token::Int(i)),
+                    Err(_) => BigInt::parse_bytes(digits.as_bytes(), radix)
+                        .map(Token::BigInt)
+                        .ok_or_else(|| self.invalid_number()),
+                };
+            }
+        }
+
+        let mut literal = String::new();
+        let mut is_float = false;
+        let mut valid = self.read_digits(10, &mut literal);
+
+        if self.ch == '.' && self.peek_char().is_ascii_digit() {
+            is_float = true;
+            literal.push('.');
+            self.read_char();
+            valid &= self.read_digits(10, &mut literal);
+        }
+
+        if self.ch == 'e' || self.ch == 'E' {
+            is_float = true;
+            literal.push('e');
             self.read_char();
+            if self.ch == '+' || self.ch == '-' {
+                literal.push(self.ch);
+                self.read_char();
+            }
+            valid &= self.read_digits(10, &mut literal);
+        }
+
+        if !self.end_of_number() || !valid {
+            return Err(self.invalid_number());
+        }
+
+        if is_float {
+            literal
+                .parse::<f64>()
+                .map(Token::Float)
+                .map_err(|_| self.invalid_number())
+        } else if let Ok(i) = literal.parse::<i64>() {
+            Ok(Token::Int(i))
+        } else {
+            literal
+                .parse::<BigInt>()
+                .map(Token::BigInt)
+                .map_err(|_| self.invalid_number())
         }
-        self.input[pos..self.position].parse::<i32>().unwrap()
     }
 
-    fn read_string(&mut self) -> String {
-        let pos: usize = self.position + 1;
-        loop {
+    /// Reads a run of digits in `radix` onto `digits`, dropping the single
+    /// underscores allowed between them. Returns false if there were no
+    /// digits or an underscore was not between two digits.
+    fn read_digits(&mut self, radix: u32, digits: &mut String) -> bool {
+        let mut valid = self.ch.is_digit(radix);
+        let mut last_underscore = false;
+        while self.ch.is_digit(radix) || self.ch == '_' {
+            if self.ch == '_' {
+                valid &= !last_underscore;
+                last_underscore = true;
+            } else {
+                digits.push(self.ch);
+                last_underscore = false;
+            }
             self.read_char();
-            if self.ch == '"' || self.ch == (0 as char) {
-                break;
+        }
+        valid && !last_underscore
+    }
+
+    /// Checks that a number isn't immediately followed by letters or digits,
+    /// as in `0b102` or `12ab`, consuming them if it is.
+    fn end_of_number(&mut self) -> bool {
+        if !(self.ch.is_alphanumeric() || self.ch == '_') {
+            return true;
+        }
+        while self.ch.is_alphanumeric() || self.ch == '_' {
+            self.read_char();
+        }
+        false
+    }
+
+    fn read_string(&mut self) -> Result<Token, LexError> {
+        let mut parts = vec![];
+        let mut current = String::new();
+        let mut error = None;
+
+        self.read_char();
+        while self.ch != '"' && self.ch != (0 as char) {
+            match self.ch {
+                '\\' => {
+                    let start = self.current_position();
+                    self.read_char();
+                    match self.read_escape() {
+                        Some(c) => current.push(c),
+                        None => {
+                            if error.is_none() && self.ch != (0 as char) {
+                                error = Some(self.invalid_escape(start));
+                            }
+                        }
+                    }
+                }
+                '$' if self.peek_char() == '{' => {
+                    self.read_char();
+                    self.read_char();
+                    let start = self.current_position();
+                    let code = self.read_interpolation();
+                    if !current.is_empty() {
+                        parts.push(StringPart::Literal(std::mem::take(&mut current)));
+                    }
+                    parts.push(StringPart::Code(code, start));
+                }
+                c => current.push(c),
             }
+            self.read_char();
         }
-        self.input[pos..self.position].to_string()
+
+        if self.ch == (0 as char) {
+            return Err(LexError::UnterminatedString(self.token_span()));
+        }
+        if let Some(e) = error {
+            self.read_char();
+            return Err(e);
+        }
+        if parts.is_empty() {
+            return Ok(Token::Str(current));
+        }
+        if !current.is_empty() {
+            parts.push(StringPart::Literal(current));
+        }
+        Ok(Token::Interpolated(parts))
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
-            self.read_char()
+    /// Builds the error for an escape sequence starting at `start` and ending
+    /// with the current character.
+    fn invalid_escape(&self, start: Position) -> LexError {
+        let escape = &self.input[start.offset - self.start_offset..self.read_position];
+        let end = Position::new(
+            self.start_offset + self.read_position,
+            self.line,
+            self.column + 1,
+        );
+        LexError::InvalidEscape(escape.to_string(), Span::new(start, end))
+    }
+
+    /// Reads the character following a `\` in a string literal, leaving the
+    /// lexer on the last character of the escape sequence.
+    fn read_escape(&mut self) -> Option<char> {
+        match self.ch {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            '$' => Some('$'),
+            'u' => {
+                if self.peek_char() != '{' {
+                    return None;
+                }
+                self.read_char();
+                let mut hex = String::new();
+                while self.peek_char().is_ascii_hexdigit() {
+                    self.read_char();
+                    hex.push(self.ch);
+                }
+                if self.peek_char() != '}' {
+                    return None;
+                }
+                self.read_char();
+                u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+            }
+            _ => None,
         }
     }
 
+    /// Reads the source of a `${...}` expression up to its closing brace,
+    /// skipping over any nested braces and string literals.
+    fn read_interpolation(&mut self) -> String {
+        let mut code = String::new();
+        let mut depth = 0;
+        while self.ch != (0 as char) {
+            match self.ch {
+                '}' if depth == 0 => break,
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                '"' => {
+                    code.push(self.ch);
+                    self.read_char();
+                    while self.ch != '"' && self.ch != (0 as char) {
+                        if self.ch == '\\' {
+                            code.push(self.ch);
+                            self.read_char();
+                        }
+                        code.push(self.ch);
+                        self.read_char();
+                    }
+                    if self.ch == (0 as char) {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            code.push(self.ch);
+            self.read_char();
+        }
+        code
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), LexError> {
+        loop {
+            match self.ch {
+                ' ' | '\t' | '\n' | '\r' => self.read_char(),
+                '#' => {
+                    let start = self.current_position();
+                    self.skip_line_comment();
+                    self.push_comment(start);
+                }
+                '/' if self.peek_char() == '*' => {
+                    let start = self.current_position();
+                    self.skip_block_comment()?;
+                    self.push_comment(start);
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn push_comment(&mut self, start: Position) {
+        let text = &self.input[start.offset - self.start_offset..self.position];
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            span: Span::new(start, self.current_position()),
+        });
+    }
+
+    fn skip_line_comment(&mut self) {
+        while self.ch != '\n' && self.ch != (0 as char) {
+            self.read_char();
+        }
+    }
+
+    /// Skips a `/* ... */` comment, which may contain nested block comments.
+    fn skip_block_comment(&mut self) -> Result<(), LexError> {
+        let start = self.current_position();
+        let mut depth = 0;
+        while self.ch != (0 as char) {
+            if self.ch == '/' && self.peek_char() == '*' {
+                depth += 1;
+                self.read_char();
+            } else if self.ch == '*' && self.peek_char() == '/' {
+                depth -= 1;
+                self.read_char();
+                if depth == 0 {
+                    self.read_char();
+                    return Ok(());
+                }
+            }
+            self.read_char();
+        }
+        Err(LexError::UnterminatedComment(Span::new(
+            start,
+            self.current_position(),
+        )))
+    }
+
     fn peek_char(&self) -> char {
-        if self.read_position >= self.input.len() {
-            return 0 as char;
+        self.char_at(self.read_position)
+    }
+}
+
+impl Iterator for Lexer {
+    type Item = Result<SpannedToken, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_token();
+        if let Ok(SpannedToken {
+            token: Token::Eof, ..
+        }) = token
+        {
+            self.finished = true;
         }
-        self.input.chars().nth(self.read_position).unwrap()
+        Some(token)
     }
 }
 
@@ -1,6 +1,9 @@
 use crate::{
-    lexer::Lexer,
-    token::Token::{self, *},
+    lexer::{error::LexError, Lexer},
+    token::{
+        Position, Span, SpannedToken, StringPart,
+        Token::{self, *},
+    },
 };
 
 #[test]
@@ -12,7 +15,7 @@ let add = fn(x, y) {
     x  + y;
 };
 
-!-/*5;
+!-/ *5;
 5 < 10 > 5;
 
 if (5 < 10) {
@@ -124,7 +127,495 @@ let result = add(five, ten);
     let mut l = Lexer::new(input.to_string());
 
     for expect in tests {
-        let tok: Token = l.next_token();
+        let tok: Token = l.next_token().unwrap().token;
         assert_eq!(expect, tok);
     }
 }
+
+#[test]
+pub fn test_logical_operators() {
+    let mut l = Lexer::new(String::from("a && b || !c"));
+    let tests = vec![
+        Ident("a".to_string()),
+        And,
+        Ident("b".to_string()),
+        Or,
+        Bang,
+        Ident("c".to_string()),
+        Eof,
+    ];
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_pipe_operator() {
+    let mut l = Lexer::new(String::from("xs |> f(1) || a|b"));
+    let tests = vec![
+        Ident("xs".to_string()),
+        Pipe,
+        Ident("f".to_string()),
+        LParen,
+        Int(1),
+        RParen,
+        Or,
+        Ident("a".to_string()),
+        Bar,
+        Ident("b".to_string()),
+        Eof,
+    ];
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_null_tokens() {
+    let mut l = Lexer::new(String::from("null ?? h?[0] f?.(x) ?"));
+    let tests = vec![
+        Null,
+        Coalesce,
+        Ident("h".to_string()),
+        OptionalLBracket,
+        Int(0),
+        RBracket,
+        Ident("f".to_string()),
+        OptionalDot,
+        LParen,
+        Ident("x".to_string()),
+        RParen,
+    ];
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+    assert!(l.next_token().is_err());
+}
+
+#[test]
+pub fn test_bitwise_operators() {
+    let mut l = Lexer::new(String::from("a & b | c ^ ~d << 1 >> 2 ** 3 * 4 <= 5"));
+    let tests = vec![
+        Ident("a".to_string()),
+        Ampersand,
+        Ident("b".to_string()),
+        Bar,
+        Ident("c".to_string()),
+        Caret,
+        Tilde,
+        Ident("d".to_string()),
+        ShiftLeft,
+        Int(1),
+        ShiftRight,
+        Int(2),
+        Power,
+        Int(3),
+        Asterisk,
+        Int(4),
+        LtEq,
+        Int(5),
+        Eof,
+    ];
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_compound_assignment() {
+    let mut l = Lexer::new(String::from("+= -= *= /= %= ** = /* c */ /"));
+    let tests = vec![
+        PlusAssign,
+        MinusAssign,
+        AsteriskAssign,
+        SlashAssign,
+        PercentAssign,
+        Power,
+        Assign,
+        Slash,
+        Eof,
+    ];
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_match_tokens() {
+    let mut l = Lexer::new(String::from("match => == = ... [a, ...rest]"));
+    let tests = vec![
+        Match,
+        FatArrow,
+        Equal,
+        Assign,
+        Ellipsis,
+        LBracket,
+        Ident(String::from("a")),
+        Comma,
+        Ellipsis,
+        Ident(String::from("rest")),
+        RBracket,
+        Eof,
+    ];
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_range_tokens() {
+    let mut l = Lexer::new(String::from("0..10 1..=2 1.5..x ..."));
+    let tests = vec![
+        Int(0),
+        DotDot,
+        Int(10),
+        Int(1),
+        DotDotEq,
+        Int(2),
+        Float(1.5),
+        DotDot,
+        Ident(String::from("x")),
+        Ellipsis,
+        Eof,
+    ];
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_token_spans() {
+    let input = "let x = 5;\n  x + \"hi\";";
+    let tests = vec![
+        (Let, (0, 1, 1), (3, 1, 4)),
+        (Ident("x".to_string()), (4, 1, 5), (5, 1, 6)),
+        (Assign, (6, 1, 7), (7, 1, 8)),
+        (Int(5), (8, 1, 9), (9, 1, 10)),
+        (SemiColon, (9, 1, 10), (10, 1, 11)),
+        (Ident("x".to_string()), (13, 2, 3), (14, 2, 4)),
+        (Plus, (15, 2, 5), (16, 2, 6)),
+        (Str(String::from("hi")), (17, 2, 7), (21, 2, 11)),
+        (SemiColon, (21, 2, 11), (22, 2, 12)),
+        (Eof, (22, 2, 12), (22, 2, 12)),
+    ];
+
+    let mut l = Lexer::new(input.to_string());
+
+    for (token, (so, sl, sc), (eo, el, ec)) in tests {
+        let tok = l.next_token().unwrap();
+        assert_eq!(
+            SpannedToken {
+                token,
+                span: Span::new(Position::new(so, sl, sc), Position::new(eo, el, ec)),
+            },
+            tok
+        );
+    }
+}
+
+#[test]
+pub fn test_comments() {
+    let input = "# a line comment
+let x = 5; # trailing comment
+/* a block
+   comment */
+x /* inline */ + 1;
+/* outer /* nested */ still a comment */
+x / 2; #no trailing newline";
+
+    let tests: Vec<Token> = vec![
+        Let,
+        Ident("x".to_string()),
+        Assign,
+        Int(5),
+        SemiColon,
+        Ident("x".to_string()),
+        Plus,
+        Int(1),
+        SemiColon,
+        Ident("x".to_string()),
+        Slash,
+        Int(2),
+        SemiColon,
+        Eof,
+    ];
+
+    let mut l = Lexer::new(input.to_string());
+
+    for expect in tests {
+        let tok: Token = l.next_token().unwrap().token;
+        assert_eq!(expect, tok);
+    }
+}
+
+#[test]
+pub fn test_comment_spans() {
+    let input = "# comment\n/* one\ntwo */ let";
+    let mut l = Lexer::new(input.to_string());
+
+    assert_eq!(
+        SpannedToken {
+            token: Let,
+            span: Span::new(Position::new(24, 3, 8), Position::new(27, 3, 11)),
+        },
+        l.next_token().unwrap()
+    );
+    assert_eq!(Eof, l.next_token().unwrap().token);
+}
+
+#[test]
+pub fn test_string_escapes() {
+    let tests = vec![
+        (r#""a\nb""#, Str(String::from("a\nb"))),
+        (r#""tab\there""#, Str(String::from("tab\there"))),
+        (r#""say \"hi\"""#, Str(String::from("say \"hi\""))),
+        (r#""back\\slash""#, Str(String::from("back\\slash"))),
+        (r#""\u{48}\u{1F600}""#, Str(String::from("H\u{1F600}"))),
+        (r#""not \${code}""#, Str(String::from("not ${code}"))),
+    ];
+
+    for (input, expect) in tests {
+        let mut l = Lexer::new(input.to_string());
+        assert_eq!(expect, l.next_token().unwrap().token);
+        assert_eq!(Eof, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_interpolated_string() {
+    let input = r#""Hello ${name}, you have ${len(h["items"])} items""#;
+    let mut l = Lexer::new(input.to_string());
+
+    assert_eq!(
+        Interpolated(vec![
+            StringPart::Literal(String::from("Hello ")),
+            StringPart::Code(String::from("name"), Position::new(9, 1, 10)),
+            StringPart::Literal(String::from(", you have ")),
+            StringPart::Code(String::from(r#"len(h["items"])"#), Position::new(27, 1, 28)),
+            StringPart::Literal(String::from(" items")),
+        ]),
+        l.next_token().unwrap().token
+    );
+    assert_eq!(Eof, l.next_token().unwrap().token);
+}
+
+#[test]
+pub fn test_float_literals() {
+    let input = "3.25 0.5 1e-9 2.5E3 1e+2 7 4.foo";
+    let tests: Vec<Token> = vec![
+        Float(3.25),
+        Float(0.5),
+        Float(1e-9),
+        Float(2500.0),
+        Float(100.0),
+        Int(7),
+        Int(4),
+    ];
+
+    let mut l = Lexer::new(input.to_string());
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+    assert_eq!(
+        Err(LexError::IllegalCharacter(
+            '.',
+            Span::new(Position::new(28, 1, 29), Position::new(29, 1, 30))
+        )),
+        l.next_token()
+    );
+    assert_eq!(Ident("foo".to_string()), l.next_token().unwrap().token);
+    assert_eq!(Eof, l.next_token().unwrap().token);
+}
+
+#[test]
+pub fn test_big_int_literals() {
+    let input = "9223372036854775807 9223372036854775808 123456789012345678901234567890";
+    let tests: Vec<Token> = vec![
+        Int(i64::MAX),
+        BigInt("9223372036854775808".parse().unwrap()),
+        BigInt("123456789012345678901234567890".parse().unwrap()),
+        Eof,
+    ];
+
+    let mut l = Lexer::new(input.to_string());
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_radix_literals_and_separators() {
+    let input =
+        "0xFF 0xff_ff 0b1010 0o755 1_000_000 0 007 1_000.000_5 1e1_0 0x7FFF_FFFF_FFFF_FFFF_F";
+    let tests: Vec<Token> = vec![
+        Int(255),
+        Int(65535),
+        Int(10),
+        Int(493),
+        Int(1_000_000),
+        Int(0),
+        Int(7),
+        Float(1000.0005),
+        Float(1e10),
+        BigInt("147573952589676412927".parse().unwrap()),
+        Eof,
+    ];
+
+    let mut l = Lexer::new(input.to_string());
+
+    for expect in tests {
+        assert_eq!(expect, l.next_token().unwrap().token);
+    }
+}
+
+#[test]
+pub fn test_malformed_number_literals() {
+    let tests = vec![
+        "0x", "0xG1", "0b102", "0o8", "1__0", "1_", "0x_1", "12ab", "1.5e", "1e+",
+    ];
+
+    for input in tests {
+        let mut l = Lexer::new(format!("{};", input));
+        match l.next_token() {
+            Err(LexError::InvalidNumber(literal, span)) => {
+                assert_eq!(input, literal);
+                assert_eq!(input.len(), span.end.offset, "input: {}", input);
+            }
+            other => panic!("input: {}, got {:?}", input, other),
+        }
+        assert_eq!(SemiColon, l.next_token().unwrap().token, "input: {}", input);
+    }
+}
+
+#[test]
+pub fn test_non_ascii_input() {
+    let input = "let s = \"héllo wörld 😀\"; s + ٣ é";
+    let tests = vec![
+        (Let, (0, 1, 1)),
+        (Ident("s".to_string()), (4, 1, 5)),
+        (Assign, (6, 1, 7)),
+        (Str(String::from("héllo wörld 😀")), (8, 1, 9)),
+        (SemiColon, (28, 1, 24)),
+        (Ident("s".to_string()), (30, 1, 26)),
+        (Plus, (32, 1, 28)),
+    ];
+
+    let mut l = Lexer::new(input.to_string());
+
+    for (token, (offset, line, column)) in tests {
+        let tok = l.next_token().unwrap();
+        assert_eq!(token, tok.token);
+        assert_eq!(Position::new(offset, line, column), tok.span.start);
+    }
+    assert_eq!(
+        Err(LexError::IllegalCharacter(
+            '٣',
+            Span::new(Position::new(34, 1, 30), Position::new(36, 1, 31))
+        )),
+        l.next_token()
+    );
+    assert_eq!(
+        Err(LexError::IllegalCharacter(
+            'é',
+            Span::new(Position::new(37, 1, 32), Position::new(39, 1, 33))
+        )),
+        l.next_token()
+    );
+    assert_eq!(Eof, l.next_token().unwrap().token);
+}
+
+#[test]
+pub fn test_empty_input() {
+    let mut l = Lexer::new(String::new());
+    assert_eq!(Eof, l.next_token().unwrap().token);
+    assert_eq!(Eof, l.next_token().unwrap().token);
+}
+
+#[test]
+pub fn test_lexer_iterator() {
+    let tokens: Vec<Token> = Lexer::new(String::from("let x = 1;"))
+        .map(|tok| tok.unwrap().token)
+        .collect();
+    assert_eq!(
+        vec![Let, Ident("x".to_string()), Assign, Int(1), SemiColon, Eof],
+        tokens
+    );
+
+    let results: Vec<bool> = Lexer::new(String::from("1 @ 2"))
+        .map(|tok| tok.is_ok())
+        .collect();
+    assert_eq!(vec![true, false, true, true], results);
+}
+
+#[test]
+pub fn test_lex_errors() {
+    let tests = vec![
+        ("@", "Illegal character `@` at line 1, column 1"),
+        (
+            "x = \"abc",
+            "Unterminated string starting at line 1, column 5",
+        ),
+        ("\"a\\", "Unterminated string starting at line 1, column 1"),
+        (
+            "1 /* open /* nested */",
+            "Unterminated block comment starting at line 1, column 3",
+        ),
+        (
+            "0b102",
+            "Invalid number literal `0b102` at line 1, column 1",
+        ),
+        (
+            r#""bad \q escape""#,
+            "Invalid escape sequence `\\q` at line 1, column 6",
+        ),
+        (
+            r#""bad \u{110000}""#,
+            "Invalid escape sequence `\\u{110000}` at line 1, column 6",
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let error = Lexer::new(input.to_string())
+            .find_map(Result::err)
+            .unwrap_or_else(|| panic!("no error for {}", input));
+        assert_eq!(expect, error.to_string());
+    }
+
+    let mut l = Lexer::new(String::from(r#""a\qb\u{110000}" 1"#));
+    assert_eq!(
+        Err(LexError::InvalidEscape(
+            String::from("\\q"),
+            Span::new(Position::new(2, 1, 3), Position::new(4, 1, 5))
+        )),
+        l.next_token()
+    );
+    assert_eq!(Int(1), l.next_token().unwrap().token);
+}
+
+#[test]
+pub fn test_large_input() {
+    let line = "let value = [1, 2.5, \"ü\"] + fn(x) { x * 2 }; # comment\n";
+    let input = line.repeat(40_000);
+    assert!(input.len() > 2_000_000);
+
+    let mut l = Lexer::new(input);
+    let mut count = 0;
+    loop {
+        let tok = l.next_token().unwrap();
+        if tok.token == Eof {
+            assert_eq!(40_001, tok.span.start.line);
+            break;
+        }
+        count += 1;
+    }
+    assert_eq!(40_000 * 21, count);
+}
@@ -5,13 +5,14 @@ pub mod ast;
 pub mod eval;
 pub mod lexer;
 pub mod parser;
+pub mod printer;
 pub mod repl;
 pub mod token;
 
 use eval::{env::Env, object::Object, Eval};
 use lexer::Lexer;
 use parser::Parser;
-use std::{cell::RefCell, env, fs, rc::Rc};
+use std::{cell::RefCell, env, fs, process, rc::Rc};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -48,6 +49,11 @@ fn main() {
         return;
     }
 
+    if args.len() != 1 && args[1].as_str() == "fmt" {
+        format_files(&args[2..]);
+        return;
+    }
+
     let user = match env::var("USER") {
         Ok(i) => i,
         Err(_e) => "there".to_string(),
@@ -59,3 +65,37 @@ fn main() {
     );
     repl::start();
 }
+
+/// Rewrites each file in the canonical style. With `--check`, files are left
+/// alone and the process fails if any of them would change.
+fn format_files(args: &[String]) {
+    let check = args.iter().any(|arg| arg == "--check");
+    let files: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    if files.is_empty() {
+        println!("Usage: hera fmt [--check] <file>...");
+        process::exit(2);
+    }
+
+    let mut failed = false;
+    for file in files {
+        let content = fs::read_to_string(file).expect("Could not read the file.");
+        match printer::format(&content) {
+            Ok(formatted) if formatted == content => {}
+            Ok(_) if check => {
+                println!("{} is not formatted", file);
+                failed = true;
+            }
+            Ok(formatted) => fs::write(file, formatted).expect("Could not write the file."),
+            Err(errors) => {
+                println!("{}:", file);
+                for e in errors.iter() {
+                    println!("\t{}", e);
+                }
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    lexer::error::LexError,
+    token::{Span, Token},
+};
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The lexer rejected the input, reported once the parser reaches it.
+    Lex(LexError),
+    UnexpectedToken {
+        expected: Token,
+        found: Token,
+        span: Span,
+    },
+    /// A closing `)`, `]` or `}` is missing. `open` is where the delimiter
+    /// it should close is.
+    MissingDelimiter {
+        delimiter: Token,
+        open: Span,
+        found: Token,
+        span: Span,
+    },
+    ExpectedExpression {
+        found: Token,
+        span: Span,
+    },
+    InvalidParameter {
+        found: Token,
+        span: Span,
+    },
+    InvalidPattern {
+        found: Token,
+        span: Span,
+    },
+    PositionalAfterKeyword(Span),
+    InvalidUpdateTarget(Span),
+    /// A block runs to the end of the input. The span is its opening `{`.
+    UnterminatedBlock(Span),
+    /// A `break` or `continue` that isn't inside a loop.
+    OutsideLoop {
+        keyword: Token,
+        span: Span,
+    },
+    EmptyInterpolation(Span),
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::Lex(e) => e.span(),
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::MissingDelimiter { span, .. }
+            | ParseError::ExpectedExpression { span, .. }
+            | ParseError::InvalidParameter { span, .. }
+            | ParseError::InvalidPattern { span, .. }
+            | ParseError::OutsideLoop { span, .. } => *span,
+            ParseError::InvalidUpdateTarget(span)
+            | ParseError::PositionalAfterKeyword(span)
+            | ParseError::UnterminatedBlock(span)
+            | ParseError::EmptyInterpolation(span) => *span,
+        }
+    }
+}
+
+impl From<LexError> for ParseError {
+    fn from(e: LexError) -> Self {
+        ParseError::Lex(e)
+    }
+}
+
+/// Describes a token for an error message.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Eof => String::from("end of input"),
+        Token::Ident(ident) if ident.is_empty() => String::from("an identifier"),
+        Token::Illegal => String::from("an illegal token"),
+        _ => format!("`{}`", token),
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Lex(e) => write!(f, "{}", e),
+            ParseError::UnexpectedToken {
+                expected,
+                found,
+                span,
+            } => write!(
+                f,
+                "Expected {}, got {} at {}",
+                describe(expected),
+                describe(found),
+                span
+            ),
+            ParseError::MissingDelimiter {
+                delimiter,
+                open,
+                found,
+                span,
+            } => {
+                let opening = match delimiter {
+                    Token::RParen => "(",
+                    Token::RBracket => "[",
+                    _ => "{",
+                };
+                write!(
+                    f,
+                    "Expected {} to match `{}` at {}, got {} at {}",
+                    describe(delimiter),
+                    opening,
+                    open,
+                    describe(found),
+                    span
+                )
+            }
+            ParseError::ExpectedExpression { found, span } => {
+                write!(
+                    f,
+                    "Expected an expression, got {} at {}",
+                    describe(found),
+                    span
+                )
+            }
+            ParseError::InvalidParameter { found, span } => write!(
+                f,
+                "Expected function parameter to be an identifier, got {} at {}",
+                describe(found),
+                span
+            ),
+            ParseError::InvalidPattern { found, span } => {
+                write!(f, "Expected a pattern, got {} at {}", describe(found), span)
+            }
+            ParseError::PositionalAfterKeyword(span) => write!(
+                f,
+                "Expected a keyword argument after keyword arguments at {}",
+                span
+            ),
+            ParseError::InvalidUpdateTarget(span) => {
+                write!(f, "Invalid update target at {}", span)
+            }
+            ParseError::UnterminatedBlock(span) => {
+                write!(f, "Unterminated block starting at {}", span)
+            }
+            ParseError::OutsideLoop { keyword, span } => {
+                write!(f, "`{}` outside of a loop at {}", keyword, span)
+            }
+            ParseError::EmptyInterpolation(span) => write!(
+                f,
+                "Expected an expression inside string interpolation at {}",
+                span
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Lex(e) => Some(e),
+            _ => None,
+        }
+    }
+}
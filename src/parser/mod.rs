@@ -1,13 +1,26 @@
+pub mod error;
+
 #[cfg(test)]
 pub mod test;
 
-use crate::{ast::*, lexer::Lexer, token::Token};
+use crate::{
+    ast::*,
+    lexer::Lexer,
+    token::{Span, SpannedToken, StringPart, Token},
+};
+use error::ParseError;
+use num_traits::ToPrimitive;
 
 pub struct Parser {
     pub l: Lexer,
     pub current_token: Token,
     pub peek_token: Token,
-    pub errors: Vec<String>,
+    pub current_span: Span,
+    pub peek_span: Span,
+    pub errors: Vec<ParseError>,
+    /// How many loops the current token is nested in, within the innermost
+    /// function, so that stray `break`s and `continue`s can be reported.
+    pub loop_depth: usize,
 }
 
 impl Parser {
@@ -16,7 +29,10 @@ impl Parser {
             l: lexer,
             current_token: Token::Eof,
             peek_token: Token::Eof,
+            current_span: Span::default(),
+            peek_span: Span::default(),
             errors: vec![],
+            loop_depth: 0,
         };
         p.next_token();
         p.next_token();
@@ -24,71 +40,136 @@ impl Parser {
     }
 
     pub fn next_token(&mut self) {
-        self.current_token = self.peek_token.clone();
-        self.peek_token = self.l.next_token();
+        let next = match self.l.next() {
+            Some(Ok(token)) => token,
+            Some(Err(e)) => {
+                let span = e.span();
+                self.errors.push(ParseError::Lex(e));
+                SpannedToken {
+                    token: Token::Illegal,
+                    span,
+                }
+            }
+            None => SpannedToken {
+                token: Token::Eof,
+                span: self.peek_span,
+            },
+        };
+        self.current_token = std::mem::replace(&mut self.peek_token, next.token);
+        self.current_span = std::mem::replace(&mut self.peek_span, next.span);
+    }
+
+    /// Wraps `node` in a span running from `start` to the current token.
+    fn spanned<T>(&self, node: T, start: Span) -> Spanned<T> {
+        Spanned::new(node, start.to(self.current_span))
     }
 
     fn token_to_precedence(tok: &Token) -> Precedence {
         match tok {
+            Token::Pipe => Precedence::Pipe,
+            Token::Coalesce => Precedence::Coalesce,
+            Token::Or => Precedence::LogicalOr,
+            Token::And => Precedence::LogicalAnd,
             Token::Equal | Token::NotEq => Precedence::Equals,
             Token::Lt | Token::LtEq => Precedence::LessGreater,
             Token::Gt | Token::GtEq => Precedence::LessGreater,
+            Token::DotDot | Token::DotDotEq => Precedence::Range,
+            Token::Bar => Precedence::BitOr,
+            Token::Caret => Precedence::BitXor,
+            Token::Ampersand => Precedence::BitAnd,
+            Token::ShiftLeft | Token::ShiftRight => Precedence::Shift,
             Token::Plus | Token::Minus => Precedence::Sum,
             Token::Slash | Token::Asterisk | Token::Percent => Precedence::Product,
-            Token::LBracket => Precedence::Index,
-            Token::LParen => Precedence::Call,
+            Token::Power => Precedence::Power,
+            Token::LBracket | Token::OptionalLBracket => Precedence::Index,
+            Token::LParen | Token::OptionalDot => Precedence::Call,
             _ => Precedence::Lowest,
         }
     }
 
+    /// Parses the whole input. A statement with a syntax error is reported
+    /// and skipped, so that all of the errors in the input are found at once.
     pub fn parse_program(&mut self) -> Program {
-        let mut statements: Vec<Statement> = vec![];
+        let mut statements: Vec<Spanned<Statement>> = vec![];
         while self.current_token != Token::Eof {
-            let stmt: Option<Statement> = self.parse_statement();
-            if stmt != None {
-                statements.push(stmt.unwrap());
+            match self.parse_statement() {
+                Some(stmt) => statements.push(stmt),
+                None => self.synchronize(),
             };
             self.next_token();
         }
         Program { statements }
     }
 
-    pub fn parse_statement(&mut self) -> Option<Statement> {
-        match self.current_token {
+    /// Skips the rest of a statement that failed to parse: up to its `;`, or
+    /// up to the next token that starts a statement or closes the enclosing
+    /// block. Blocks inside the statement are skipped as a whole.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.current_token {
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth > 0 => depth -= 1,
+                Token::SemiColon if depth == 0 => return,
+                Token::Eof => return,
+                _ => {}
+            }
+            let boundary = matches!(
+                self.peek_token,
+                Token::Let
+                    | Token::Update
+                    | Token::Return
+                    | Token::Import
+                    | Token::While
+                    | Token::For
+                    | Token::RBrace
+                    | Token::Eof
+            );
+            if depth == 0 && boundary {
+                return;
+            }
+            self.next_token();
+        }
+    }
+
+    pub fn parse_statement(&mut self) -> Option<Spanned<Statement>> {
+        let start = self.current_span;
+        let statement = match self.current_token {
             Token::Let => self.parse_let_statement(),
             Token::Return => self.parse_return_statement(),
             Token::Import => self.parse_import_statement(),
             Token::Update => self.parse_update_statement(),
+            Token::While => self.parse_while_statement(),
+            Token::For => self.parse_for_statement(),
+            Token::Break => self.parse_loop_control_statement(Statement::Break),
+            Token::Continue => self.parse_loop_control_statement(Statement::Continue),
+            Token::Function if matches!(self.peek_token, Token::Ident(_)) => {
+                self.parse_fn_statement()
+            }
             // _ => panic!("Illegal token found."),
             _ => self.parse_expression_statement(),
-        }
+        };
+        statement.map(|s| self.spanned(s, start))
     }
 
     pub fn parse_expression_statement(&mut self) -> Option<Statement> {
-        match self.parse_expression(Precedence::Lowest) {
-            Some(expression) => {
-                if self.peek_token_is(&Token::SemiColon) {
-                    self.next_token();
-                }
-                Some(Statement::Expression(expression))
-            }
-            None => None,
+        let expression = self.parse_expression(Precedence::Lowest)?;
+        if self.peek_token_is(&Token::SemiColon) {
+            self.next_token();
         }
+        Some(Statement::Expression(expression))
     }
 
     pub fn parse_let_statement(&mut self) -> Option<Statement> {
         match &self.peek_token {
-            Token::Ident(_) => self.next_token(),
+            Token::Ident(_) | Token::LBracket | Token::LBrace => self.next_token(),
             _ => {
                 self.peek_error(Token::Ident(String::new()));
                 return None;
             }
         }
 
-        let name: Ident = match self.parse_ident() {
-            Some(Expression::Ident(ref mut s)) => s.clone(),
-            _ => return None,
-        };
+        let pattern = self.parse_pattern()?;
 
         if !self.expect_peek(Token::Assign) {
             return None;
@@ -96,19 +177,92 @@ impl Parser {
 
         self.next_token();
 
-        let lit: Expression = match self.parse_expression(Precedence::Lowest) {
-            Some(e) => e,
-            None => return None,
+        let lit = self.parse_expression(Precedence::Lowest)?;
+        self.skip_semicolon();
+
+        Some(Statement::Let(pattern, lit))
+    }
+
+    pub fn parse_fn_statement(&mut self) -> Option<Statement> {
+        self.next_token();
+        let name = self.parse_param_name()?;
+        let (params, rest, body) = self.parse_fn_definition()?;
+        self.skip_semicolon();
+
+        Some(Statement::Fn {
+            name,
+            params,
+            rest,
+            body,
+        })
+    }
+
+    pub fn parse_update_statement(&mut self) -> Option<Statement> {
+        match &self.peek_token {
+            Token::Ident(_) => self.next_token(),
+            _ => {
+                self.peek_error(Token::Ident(String::new()));
+                return None;
+            }
+        }
+
+        let target = self.parse_expression(Precedence::Lowest)?;
+        if !is_update_target(&target.node) {
+            self.errors
+                .push(ParseError::InvalidUpdateTarget(target.span));
+            return None;
+        }
+
+        let operator = match self.peek_token {
+            Token::Assign => None,
+            Token::PlusAssign => Some(Infix::Plus),
+            Token::MinusAssign => Some(Infix::Minus),
+            Token::AsteriskAssign => Some(Infix::Multiply),
+            Token::SlashAssign => Some(Infix::Divide),
+            Token::PercentAssign => Some(Infix::Modulus),
+            _ => {
+                self.peek_error(Token::Assign);
+                return None;
+            }
         };
+        self.next_token();
+        self.next_token();
 
-        while !self.current_token_is(Token::SemiColon) {
-            self.next_token();
+        let value = self.parse_expression(Precedence::Lowest)?;
+        self.skip_semicolon();
+
+        Some(Statement::Update {
+            target,
+            operator,
+            value,
+        })
+    }
+
+    pub fn parse_while_statement(&mut self) -> Option<Statement> {
+        if !self.expect_peek(Token::LParen) {
+            return None;
         }
+        let open = self.current_span;
+
+        self.next_token();
 
-        Some(Statement::Let(name, lit))
+        let condition = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_closing(Token::RParen, open) || !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+
+        let body = self.parse_loop_body()?;
+
+        Some(Statement::While { condition, body })
     }
 
-    pub fn parse_update_statement(&mut self) -> Option<Statement> {
+    pub fn parse_for_statement(&mut self) -> Option<Statement> {
+        if !self.expect_peek(Token::LParen) {
+            return None;
+        }
+        let open = self.current_span;
+
         match &self.peek_token {
             Token::Ident(_) => self.next_token(),
             _ => {
@@ -117,87 +271,144 @@ impl Parser {
             }
         }
 
-        let name: Ident = match self.parse_ident() {
-            Some(Expression::Ident(ref mut s)) => s.clone(),
+        let ident: Ident = match self.parse_ident() {
+            Some(Expression::Ident(ident)) => ident,
             _ => return None,
         };
 
-        if !self.expect_peek(Token::Assign) {
+        if !self.expect_peek(Token::In) {
             return None;
         }
 
         self.next_token();
 
-        let lit: Expression = match self.parse_expression(Precedence::Lowest) {
-            Some(e) => e,
-            None => return None,
-        };
+        let iterable = self.parse_expression(Precedence::Lowest)?;
 
-        while !self.current_token_is(Token::SemiColon) {
-            self.next_token();
+        if !self.expect_closing(Token::RParen, open) || !self.expect_peek(Token::LBrace) {
+            return None;
         }
 
-        Some(Statement::Update(name, lit))
+        let body = self.parse_loop_body()?;
+
+        Some(Statement::For {
+            ident,
+            iterable,
+            body,
+        })
     }
 
-    pub fn parse_return_statement(&mut self) -> Option<Statement> {
-        self.next_token();
+    fn parse_loop_body(&mut self) -> Option<BlockStatement> {
+        self.loop_depth += 1;
+        let body = self.parse_block_statement();
+        self.loop_depth -= 1;
 
-        let exp = match self.parse_expression(Precedence::Lowest) {
-            Some(e) => e,
-            None => return None,
-        };
+        self.skip_semicolon();
+        body
+    }
 
-        while !self.current_token_is(Token::SemiColon) {
-            self.next_token();
+    /// Parses a `break` or `continue`, which may only appear inside a loop.
+    fn parse_loop_control_statement(&mut self, statement: Statement) -> Option<Statement> {
+        if self.loop_depth == 0 {
+            self.errors.push(ParseError::OutsideLoop {
+                keyword: self.current_token.clone(),
+                span: self.current_span,
+            });
+            return None;
         }
 
+        self.skip_semicolon();
+        Some(statement)
+    }
+
+    pub fn parse_return_statement(&mut self) -> Option<Statement> {
+        self.next_token();
+
+        let exp = self.parse_expression(Precedence::Lowest)?;
+        self.skip_semicolon();
+
         Some(Statement::Return(exp))
     }
 
     pub fn parse_import_statement(&mut self) -> Option<Statement> {
+        let lib = match self.peek_token {
+            Token::Ident(ref ident) => ident.clone(),
+            _ => {
+                self.peek_error(Token::Ident(String::new()));
+                return None;
+            }
+        };
         self.next_token();
+        self.skip_semicolon();
 
-        let lib = match self.current_token {
-            Token::Ident(ref mut ident) => ident.clone(),
-            _ => return None,
-        };
-        while !self.current_token_is(Token::SemiColon) {
+        Some(Statement::Import(Ident(lib)))
+    }
+
+    /// Consumes the `;` ending a statement, if there is one.
+    fn skip_semicolon(&mut self) {
+        if self.peek_token_is(&Token::SemiColon) {
             self.next_token();
         }
-        Some(Statement::Import(Ident(lib)))
     }
 
-    fn parse_block_statement(&mut self) -> BlockStatement {
+    /// Parses the statements of a block, starting at its `{`. Statements with
+    /// errors are skipped like in `parse_program`, but a block missing its
+    /// closing `}` is an error.
+    fn parse_block_statement(&mut self) -> Option<BlockStatement> {
+        let start = self.current_span;
         self.next_token();
 
         let mut statements = vec![];
-        while !self.current_token_is(Token::RBrace) && !self.current_token_is(Token::Eof) {
-            if let Some(s) = self.parse_statement() {
-                statements.push(s);
+        while !self.current_token_is(Token::RBrace) {
+            if self.current_token_is(Token::Eof) {
+                self.errors.push(ParseError::UnterminatedBlock(start));
+                return None;
+            }
+            match self.parse_statement() {
+                Some(s) => statements.push(s),
+                None => self.synchronize(),
             }
             self.next_token();
         }
 
-        statements
+        Some(statements)
     }
 
-    fn parse_expression(&mut self, precedence: Precedence) -> Option<Expression> {
+    fn parse_expression(&mut self, precedence: Precedence) -> Option<Spanned<Expression>> {
+        let start = self.current_span;
+        let errors = self.errors.len();
+
         // prefix
-        let mut left: Option<Expression> = match self.current_token {
+        let left = match self.current_token {
             Token::Int(_) => self.parse_int_literal(),
+            Token::BigInt(_) => self.parse_big_int_literal(),
+            Token::Float(_) => self.parse_float_literal(),
             Token::Bool(_) => self.parse_bool_literal(),
+            Token::Null => Some(Expression::Literal(Literal::Null)),
             Token::Str(_) => self.parse_string_literal(),
+            Token::Interpolated(_) => self.parse_interpolated_string(),
             Token::LBracket => self.parse_array_literal(),
             Token::Ident(_) => self.parse_ident(),
-            Token::Bang | Token::Minus | Token::Plus => self.parse_prefix_expression(),
+            Token::Bang | Token::Minus | Token::Plus | Token::Tilde => {
+                self.parse_prefix_expression()
+            }
             Token::LParen => self.parse_grouped_expression(),
             Token::If => self.parse_if_expression(),
             Token::Function => self.parse_fn_expression(),
+            Token::Match => self.parse_match_expression(),
             Token::LBrace => self.parse_hash_literal(),
-            _ => {
-                // TODO: add function call here
-                None
+            _ => None,
+        };
+        let mut left = match left {
+            Some(e) => self.spanned(e, start),
+            None => {
+                // the lexer has already reported an illegal token
+                if self.errors.len() == errors && !self.current_token_is(Token::Illegal) {
+                    self.errors.push(ParseError::ExpectedExpression {
+                        found: self.current_token.clone(),
+                        span: self.current_span,
+                    });
+                }
+                return None;
             }
         };
 
@@ -214,23 +425,50 @@ impl Parser {
                 | Token::Lt
                 | Token::LtEq
                 | Token::Gt
-                | Token::GtEq => {
+                | Token::GtEq
+                | Token::And
+                | Token::Or
+                | Token::Pipe
+                | Token::Coalesce
+                | Token::Ampersand
+                | Token::Bar
+                | Token::Caret
+                | Token::ShiftLeft
+                | Token::ShiftRight
+                | Token::Power => {
+                    self.next_token();
+                    let expr = self.parse_infix_expression(left)?;
+                    left = self.spanned(expr, start);
+                }
+                Token::DotDot | Token::DotDotEq => {
                     self.next_token();
-                    left = self.parse_infix_expression(left.unwrap());
+                    let expr = self.parse_range_expression(left)?;
+                    left = self.spanned(expr, start);
                 }
                 Token::LParen => {
                     self.next_token();
-                    left = self.parse_call_expression(left.unwrap());
+                    let expr = self.parse_call_expression(left, false)?;
+                    left = self.spanned(expr, start);
+                }
+                Token::OptionalDot => {
+                    self.next_token();
+                    if !self.expect_peek(Token::LParen) {
+                        return None;
+                    }
+                    let expr = self.parse_call_expression(left, true)?;
+                    left = self.spanned(expr, start);
                 }
-                Token::LBracket => {
+                Token::LBracket | Token::OptionalLBracket => {
                     self.next_token();
-                    left = self.parse_index_expression(left.unwrap());
+                    let optional = self.current_token_is(Token::OptionalLBracket);
+                    let expr = self.parse_index_expression(left, optional)?;
+                    left = self.spanned(expr, start);
                 }
-                _ => return left,
+                _ => return Some(left),
             }
         }
 
-        left
+        Some(left)
     }
 
     fn parse_int_literal(&mut self) -> Option<Expression> {
@@ -240,6 +478,20 @@ impl Parser {
         }
     }
 
+    fn parse_big_int_literal(&mut self) -> Option<Expression> {
+        match self.current_token {
+            Token::BigInt(ref int) => Some(Expression::Literal(Literal::BigInt(int.clone()))),
+            _ => None,
+        }
+    }
+
+    fn parse_float_literal(&mut self) -> Option<Expression> {
+        match self.current_token {
+            Token::Float(float) => Some(Expression::Literal(Literal::Float(float))),
+            _ => None,
+        }
+    }
+
     fn parse_bool_literal(&mut self) -> Option<Expression> {
         match self.current_token {
             Token::Bool(boolean) => Some(Expression::Literal(Literal::Bool(boolean))),
@@ -254,41 +506,73 @@ impl Parser {
         }
     }
 
+    fn parse_interpolated_string(&mut self) -> Option<Expression> {
+        let parts = match self.current_token {
+            Token::Interpolated(ref parts) => parts.clone(),
+            _ => return None,
+        };
+
+        let mut exprs = vec![];
+        for part in parts {
+            match part {
+                StringPart::Literal(s) => exprs.push(Spanned::new(
+                    Expression::Literal(Literal::String(s)),
+                    self.current_span,
+                )),
+                StringPart::Code(code, start) => {
+                    let mut parser = Parser::new(Lexer::new_at(code, start));
+                    let expr = if parser.current_token_is(Token::Eof) {
+                        parser
+                            .errors
+                            .push(ParseError::EmptyInterpolation(parser.current_span));
+                        None
+                    } else {
+                        parser.parse_expression(Precedence::Lowest)
+                    };
+                    if expr.is_some() && !parser.peek_token_is(&Token::Eof) {
+                        parser.peek_error(Token::Eof);
+                    }
+                    self.errors.append(&mut parser.errors);
+                    exprs.push(expr?);
+                }
+            }
+        }
+
+        Some(Expression::Interpolated(exprs))
+    }
+
     fn parse_array_literal(&mut self) -> Option<Expression> {
         self.parse_expression_list(Token::RBracket)
             .map(|list| Expression::Literal(Literal::Array(list)))
     }
 
     fn parse_hash_literal(&mut self) -> Option<Expression> {
+        let open = self.current_span;
         let mut hash = vec![];
         while !self.peek_token_is(&Token::RBrace) {
             self.next_token();
-            let key = match self.parse_expression(Precedence::Lowest) {
-                Some(o) => o,
-                None => return None,
-            };
+            let key = self.parse_expression(Precedence::Lowest)?;
             if !self.expect_peek(Token::Colon) {
                 return None;
             }
 
             self.next_token();
-            let val = match self.parse_expression(Precedence::Lowest) {
-                Some(o) => o,
-                None => return None,
-            };
+            let val = self.parse_expression(Precedence::Lowest)?;
             hash.push((key, val));
-            if !self.peek_token_is(&Token::RBrace) && !self.expect_peek(Token::Comma) {
-                return None;
+            if !self.peek_token_is(&Token::Comma) {
+                break;
             }
+            self.next_token();
         }
-        if !self.expect_peek(Token::RBrace) {
+        if !self.expect_closing(Token::RBrace, open) {
             return None;
         }
 
         Some(Expression::Literal(Literal::Hash(hash)))
     }
 
-    fn parse_expression_list(&mut self, end: Token) -> Option<Vec<Expression>> {
+    fn parse_expression_list(&mut self, end: Token) -> Option<Vec<Spanned<Expression>>> {
+        let open = self.current_span;
         let mut list = vec![];
         if self.peek_token_is(&end) {
             self.next_token();
@@ -296,24 +580,18 @@ impl Parser {
         }
 
         self.next_token();
-        match self.parse_expression(Precedence::Lowest) {
-            Some(a) => list.push(a),
-            None => return None,
-        };
+        list.push(self.parse_expression(Precedence::Lowest)?);
         while self.peek_token_is(&Token::Comma) {
             self.next_token();
-            if self.peek_token_is(&Token::RBracket) {
+            if self.peek_token_is(&end) {
                 break;
             }
             self.next_token();
 
-            match self.parse_expression(Precedence::Lowest) {
-                Some(expr) => list.push(expr),
-                None => return None,
-            }
+            list.push(self.parse_expression(Precedence::Lowest)?);
         }
 
-        if !self.expect_peek(end) {
+        if !self.expect_closing(end, open) {
             return None;
         }
 
@@ -332,6 +610,7 @@ impl Parser {
             Token::Bang => Prefix::Not,
             Token::Minus => Prefix::Minus,
             Token::Plus => Prefix::Plus,
+            Token::Tilde => Prefix::BitNot,
             _ => return None,
         };
 
@@ -341,7 +620,7 @@ impl Parser {
             .map(|expr| Expression::Prefix(prefix, Box::new(expr)))
     }
 
-    fn parse_infix_expression(&mut self, left: Expression) -> Option<Expression> {
+    fn parse_infix_expression(&mut self, left: Spanned<Expression>) -> Option<Expression> {
         let infix = match self.current_token {
             Token::Plus => Infix::Plus,
             Token::Minus => Infix::Minus,
@@ -354,54 +633,91 @@ impl Parser {
             Token::Gt => Infix::GreaterThan,
             Token::LtEq => Infix::LessThanEqual,
             Token::GtEq => Infix::GreaterThanEqual,
+            Token::And => Infix::And,
+            Token::Or => Infix::Or,
+            Token::Pipe => Infix::Pipe,
+            Token::Coalesce => Infix::Coalesce,
+            Token::Ampersand => Infix::BitAnd,
+            Token::Bar => Infix::BitOr,
+            Token::Caret => Infix::BitXor,
+            Token::ShiftLeft => Infix::ShiftLeft,
+            Token::ShiftRight => Infix::ShiftRight,
+            Token::Power => Infix::Power,
             _ => return None,
         };
 
-        let precedence = self.current_token_precedence();
+        // `**` is right associative, so its right-hand side may itself be a `**`
+        let precedence = match infix {
+            Infix::Power => Precedence::Prefix,
+            _ => self.current_token_precedence(),
+        };
         self.next_token();
 
         self.parse_expression(precedence)
             .map(|e| Expression::Infix(infix, Box::new(left), Box::new(e)))
     }
 
+    /// Parses the rest of a range after its `..` or `..=`. `step` is only a
+    /// keyword here, so it can still be used as a name elsewhere.
+    fn parse_range_expression(&mut self, start: Spanned<Expression>) -> Option<Expression> {
+        let inclusive = self.current_token_is(Token::DotDotEq);
+        self.next_token();
+        let end = self.parse_expression(Precedence::Range)?;
+
+        let mut step = None;
+        if matches!(self.peek_token, Token::Ident(ref name) if name == "step") {
+            self.next_token();
+            self.next_token();
+            step = Some(Box::new(self.parse_expression(Precedence::Range)?));
+        }
+
+        Some(Expression::Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive,
+            step,
+        })
+    }
+
     fn parse_grouped_expression(&mut self) -> Option<Expression> {
+        let open = self.current_span;
         self.next_token();
-        let exp = self.parse_expression(Precedence::Lowest);
-        if !self.expect_peek(Token::RParen) {
+        let exp = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_closing(Token::RParen, open) {
             return None;
         }
-        exp
+        Some(exp.node)
     }
 
     fn parse_if_expression(&mut self) -> Option<Expression> {
         if !self.expect_peek(Token::LParen) {
             return None;
         }
+        let open = self.current_span;
 
         self.next_token();
 
-        let expr: Expression = match self.parse_expression(Precedence::Lowest) {
-            Some(e) => e,
-            None => return None,
-        };
+        let expr = self.parse_expression(Precedence::Lowest)?;
 
-        if !self.expect_peek(Token::RParen) || !self.expect_peek(Token::LBrace) {
+        if !self.expect_closing(Token::RParen, open) || !self.expect_peek(Token::LBrace) {
             return None;
         }
 
-        let cons: Vec<Statement> = self.parse_block_statement();
-        let mut alternative: Option<Vec<Statement>> = None;
+        let cons: BlockStatement = self.parse_block_statement()?;
+        let mut alternative: Option<BlockStatement> = None;
         if self.peek_token_is(&Token::Else) {
             self.next_token();
 
             if self.peek_token_is(&Token::If) {
                 self.next_token();
-                let else_if = self.parse_if_expression();
-                alternative = Some(vec![Statement::Expression(else_if.unwrap())]);
+                let start = self.current_span;
+                let else_if = self.parse_if_expression()?;
+                let else_if = self.spanned(else_if, start);
+                alternative = Some(vec![self.spanned(Statement::Expression(else_if), start)]);
             } else if !self.expect_peek(Token::LBrace) {
                 return None;
             } else {
-                alternative = Some(self.parse_block_statement())
+                alternative = Some(self.parse_block_statement()?)
             };
         }
 
@@ -412,113 +728,365 @@ impl Parser {
         })
     }
 
-    fn parse_fn_expression(&mut self) -> Option<Expression> {
+    fn parse_match_expression(&mut self) -> Option<Expression> {
         if !self.expect_peek(Token::LParen) {
             return None;
         }
-        let params = match self.parse_fn_params() {
-            Some(s) => s,
-            None => return None,
-        };
+        let open = self.current_span;
         self.next_token();
-        let body = self.parse_block_statement();
+        let subject = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_closing(Token::RParen, open) || !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+
+        let open = self.current_span;
+        let mut arms = vec![];
+        while !self.peek_token_is(&Token::RBrace) {
+            self.next_token();
+            let start = self.current_span;
+            let arm = self.parse_match_arm()?;
+            let is_block = self.current_token_is(Token::RBrace);
+            arms.push(self.spanned(arm, start));
+
+            // the comma after an arm is optional only after a block
+            if self.peek_token_is(&Token::Comma) {
+                self.next_token();
+            } else if !is_block {
+                break;
+            }
+        }
+        if !self.expect_closing(Token::RBrace, open) {
+            return None;
+        }
 
-        Some(Expression::Fn { params, body })
+        Some(Expression::Match {
+            subject: Box::new(subject),
+            arms,
+        })
     }
 
-    fn parse_fn_params(&mut self) -> Option<Vec<Ident>> {
-        let mut idents: Vec<Ident> = vec![];
-        if self.peek_token_is(&Token::RParen) {
+    fn parse_match_arm(&mut self) -> Option<MatchArm> {
+        let pattern = self.parse_pattern()?;
+        let mut guard = None;
+        if self.peek_token_is(&Token::If) {
+            self.next_token();
             self.next_token();
-            return Some(idents);
+            guard = Some(self.parse_expression(Precedence::Lowest)?);
+        }
+        if !self.expect_peek(Token::FatArrow) {
+            return None;
         }
 
         self.next_token();
+        let body = if self.current_token_is(Token::LBrace) {
+            self.parse_block_statement()?
+        } else {
+            let start = self.current_span;
+            let expr = self.parse_expression(Precedence::Lowest)?;
+            vec![self.spanned(Statement::Expression(expr), start)]
+        };
+
+        Some(MatchArm {
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    /// Parses a pattern starting at the current token.
+    fn parse_pattern(&mut self) -> Option<Pattern> {
         match self.current_token {
-            Token::Ident(ref mut ident) => idents.push(Ident(ident.clone())),
+            Token::Ident(ref ident) if ident == "_" => Some(Pattern::Wildcard),
+            Token::Ident(ref ident) => Some(Pattern::Ident(Ident(ident.clone()))),
+            Token::LBracket => self.parse_array_pattern(),
+            Token::LBrace => self.parse_hash_pattern(),
+            _ => self.parse_literal_pattern().map(Pattern::Literal),
+        }
+    }
+
+    /// Parses a string, number or boolean literal, where numbers may be
+    /// negative.
+    fn parse_literal_pattern(&mut self) -> Option<Spanned<Literal>> {
+        let start = self.current_span;
+        let negative = self.current_token_is(Token::Minus);
+        if negative {
+            self.next_token();
+        }
+        let literal = match (&self.current_token, negative) {
+            (Token::Int(i), _) if negative => Literal::Int(-i),
+            (Token::BigInt(i), _) if negative => {
+                let i = -i;
+                i.to_i64().map_or(Literal::BigInt(i), Literal::Int)
+            }
+            (Token::Float(x), _) if negative => Literal::Float(-x),
+            (Token::Int(i), false) => Literal::Int(*i),
+            (Token::BigInt(i), false) => Literal::BigInt(i.clone()),
+            (Token::Float(x), false) => Literal::Float(*x),
+            (Token::Str(s), false) => Literal::String(s.clone()),
+            (Token::Bool(b), false) => Literal::Bool(*b),
+            (Token::Null, false) => Literal::Null,
             _ => {
-                self.errors.push(String::from(
-                    "Expected function parameter to be an identifier.",
-                ));
+                self.errors.push(ParseError::InvalidPattern {
+                    found: self.current_token.clone(),
+                    span: self.current_span,
+                });
                 return None;
             }
         };
+        Some(self.spanned(literal, start))
+    }
 
-        while self.peek_token_is(&Token::Comma) {
+    fn parse_array_pattern(&mut self) -> Option<Pattern> {
+        let open = self.current_span;
+        let mut items = vec![];
+        let mut rest = None;
+        while !self.peek_token_is(&Token::RBracket) {
+            self.next_token();
+            if self.current_token_is(Token::Ellipsis) {
+                let pattern = if self.peek_token_is(&Token::RBracket) {
+                    Pattern::Wildcard
+                } else {
+                    self.next_token();
+                    self.parse_pattern()?
+                };
+                rest = Some(Box::new(pattern));
+                break;
+            }
+            items.push(self.parse_pattern()?);
+            if !self.peek_token_is(&Token::Comma) {
+                break;
+            }
             self.next_token();
+        }
+        if !self.expect_closing(Token::RBracket, open) {
+            return None;
+        }
+
+        Some(Pattern::Array { items, rest })
+    }
+
+    fn parse_hash_pattern(&mut self) -> Option<Pattern> {
+        let open = self.current_span;
+        let mut pairs = vec![];
+        while !self.peek_token_is(&Token::RBrace) {
             self.next_token();
             match self.current_token {
-                Token::Ident(ref mut ident) => idents.push(Ident(ident.clone())),
-                _ => return None,
-            };
+                Token::Ident(ref ident) if !self.peek_token_is(&Token::Colon) => {
+                    let key = Spanned::new(Literal::String(ident.clone()), self.current_span);
+                    pairs.push((key, Pattern::Ident(Ident(ident.clone()))));
+                }
+                _ => {
+                    let key = self.parse_literal_pattern()?;
+                    if !self.expect_peek(Token::Colon) {
+                        return None;
+                    }
+                    self.next_token();
+                    pairs.push((key, self.parse_pattern()?));
+                }
+            }
+            if !self.peek_token_is(&Token::Comma) {
+                break;
+            }
+            self.next_token();
         }
+        if !self.expect_closing(Token::RBrace, open) {
+            return None;
+        }
+
+        Some(Pattern::Hash(pairs))
+    }
+
+    fn parse_fn_expression(&mut self) -> Option<Expression> {
+        let (params, rest, body) = self.parse_fn_definition()?;
+        Some(Expression::Fn { params, rest, body })
+    }
 
-        if !self.expect_peek(Token::RParen) {
+    /// Parses the `(params) { body }` that follows `fn` or `fn name`.
+    #[allow(clippy::type_complexity)]
+    fn parse_fn_definition(&mut self) -> Option<(Vec<Param>, Option<Ident>, BlockStatement)> {
+        if !self.expect_peek(Token::LParen) {
             return None;
         }
+        let (params, rest) = self.parse_fn_params()?;
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let body = self.parse_block_statement();
+        self.loop_depth = loop_depth;
 
-        Some(idents)
+        Some((params, rest, body?))
     }
 
-    fn parse_call_expression(&mut self, left: Expression) -> Option<Expression> {
-        let args = match self.parse_call_arguments() {
-            Some(e) => e,
-            None => return None,
-        };
+    fn parse_fn_params(&mut self) -> Option<(Vec<Param>, Option<Ident>)> {
+        let open = self.current_span;
+        let mut params = vec![];
+        let mut rest = None;
+        if self.peek_token_is(&Token::RParen) {
+            self.next_token();
+            return Some((params, rest));
+        }
+
+        loop {
+            self.next_token();
+            if self.current_token_is(Token::Ellipsis) {
+                self.next_token();
+                rest = Some(self.parse_param_name()?);
+                break;
+            }
+            let name = self.parse_param_name()?;
+            let mut default = None;
+            if self.peek_token_is(&Token::Assign) {
+                self.next_token();
+                self.next_token();
+                default = Some(self.parse_expression(Precedence::Lowest)?);
+            }
+            params.push(Param { name, default });
+            if !self.peek_token_is(&Token::Comma) {
+                break;
+            }
+            self.next_token();
+        }
+
+        if !self.expect_closing(Token::RParen, open) {
+            return None;
+        }
+
+        Some((params, rest))
+    }
+
+    fn parse_param_name(&mut self) -> Option<Ident> {
+        match self.current_token {
+            Token::Ident(ref ident) => Some(Ident(ident.clone())),
+            _ => {
+                self.errors.push(ParseError::InvalidParameter {
+                    found: self.current_token.clone(),
+                    span: self.current_span,
+                });
+                None
+            }
+        }
+    }
+
+    fn parse_call_expression(
+        &mut self,
+        left: Spanned<Expression>,
+        optional: bool,
+    ) -> Option<Expression> {
+        let (args, kwargs) = self.parse_call_arguments()?;
 
         Some(Expression::Call {
             function: Box::new(left),
             args,
+            kwargs,
+            optional,
         })
     }
 
-    fn parse_call_arguments(&mut self) -> Option<Vec<Expression>> {
-        let mut args: Vec<Expression> = vec![];
+    #[allow(clippy::type_complexity)]
+    fn parse_call_arguments(
+        &mut self,
+    ) -> Option<(Vec<Spanned<Expression>>, Vec<(Ident, Spanned<Expression>)>)> {
+        let open = self.current_span;
+        let mut args = vec![];
+        let mut kwargs = vec![];
 
         if self.peek_token_is(&Token::RParen) {
             self.next_token();
-            return Some(args);
+            return Some((args, kwargs));
         }
 
-        self.next_token();
-        match self.parse_expression(Precedence::Lowest) {
-            Some(e) => args.push(e),
-            None => return None,
-        };
-
-        while self.peek_token_is(&Token::Comma) {
+        loop {
             self.next_token();
+            match self.current_token {
+                Token::Ident(ref name) if self.peek_token_is(&Token::Colon) => {
+                    let name = Ident(name.clone());
+                    self.next_token();
+                    self.next_token();
+                    kwargs.push((name, self.parse_expression(Precedence::Lowest)?));
+                }
+                _ if !kwargs.is_empty() => {
+                    self.errors
+                        .push(ParseError::PositionalAfterKeyword(self.current_span));
+                    return None;
+                }
+                _ => args.push(self.parse_expression(Precedence::Lowest)?),
+            }
+            if !self.peek_token_is(&Token::Comma) {
+                break;
+            }
             self.next_token();
-
-            match self.parse_expression(Precedence::Lowest) {
-                Some(e) => args.push(e),
-                None => return None,
-            };
+            if self.peek_token_is(&Token::RParen) {
+                break;
+            }
         }
 
-        if !self.expect_peek(Token::RParen) {
+        if !self.expect_closing(Token::RParen, open) {
             return None;
         }
 
-        Some(args)
+        Some((args, kwargs))
     }
 
-    fn parse_index_expression(&mut self, left: Expression) -> Option<Expression> {
+    /// Parses an index, or a slice once there is a `:` inside the brackets.
+    fn parse_index_expression(
+        &mut self,
+        left: Spanned<Expression>,
+        optional: bool,
+    ) -> Option<Expression> {
+        let open = self.current_span;
+        let start = self.parse_slice_bound()?;
+        if !self.peek_token_is(&Token::Colon) {
+            let index = match start {
+                Some(index) => index,
+                // nothing between the brackets, let the expression parser
+                // report it
+                None => {
+                    self.next_token();
+                    Box::new(self.parse_expression(Precedence::Lowest)?)
+                }
+            };
+            if !self.expect_closing(Token::RBracket, open) {
+                return None;
+            }
+            return Some(Expression::Index {
+                left: Box::new(left),
+                index,
+                optional,
+            });
+        }
+
         self.next_token();
-        let expr = match self.parse_expression(Precedence::Lowest) {
-            Some(e) => e,
-            None => return None,
-        };
-        if !self.expect_peek(Token::RBracket) {
+        let end = self.parse_slice_bound()?;
+        let mut step = None;
+        if self.peek_token_is(&Token::Colon) {
+            self.next_token();
+            step = self.parse_slice_bound()?;
+        }
+        if !self.expect_closing(Token::RBracket, open) {
             return None;
         }
 
-        Some(Expression::Index {
+        Some(Expression::Slice {
             left: Box::new(left),
-            index: Box::new(expr),
+            start,
+            end,
+            step,
+            optional,
         })
     }
 
+    /// Parses the expression for one part of a slice, or nothing when the
+    /// part is left out.
+    fn parse_slice_bound(&mut self) -> Option<Option<Box<Spanned<Expression>>>> {
+        if self.peek_token_is(&Token::Colon) || self.peek_token_is(&Token::RBracket) {
+            return Some(None);
+        }
+        self.next_token();
+        let expr = self.parse_expression(Precedence::Lowest)?;
+        Some(Some(Box::new(expr)))
+    }
+
     fn peek_token_is(&self, t: &Token) -> bool {
         self.peek_token == *t
     }
@@ -542,13 +1110,28 @@ impl Parser {
         }
     }
 
+    /// Like `expect_peek` for the delimiter closing the one at `open`.
+    fn expect_closing(&mut self, delimiter: Token, open: Span) -> bool {
+        if self.peek_token_is(&delimiter) {
+            self.next_token();
+            return true;
+        }
+
+        self.errors.push(ParseError::MissingDelimiter {
+            delimiter,
+            open,
+            found: self.peek_token.clone(),
+            span: self.peek_span,
+        });
+        false
+    }
+
     fn peek_error(&mut self, t: Token) {
-        let msg = format!(
-            "Expected next token to be {expected}, got {found}",
-            expected = t,
-            found = self.peek_token
-        );
-        self.errors.push(msg);
+        self.errors.push(ParseError::UnexpectedToken {
+            expected: t,
+            found: self.peek_token.clone(),
+            span: self.peek_span,
+        });
     }
 
     fn current_token_precedence(&mut self) -> Precedence {
@@ -559,3 +1142,17 @@ impl Parser {
         Self::token_to_precedence(&self.peek_token)
     }
 }
+
+/// Whether `update` can assign to `expr`: an identifier, or an index into
+/// something that can be assigned to.
+fn is_update_target(expr: &Expression) -> bool {
+    match expr {
+        Expression::Ident(_) => true,
+        Expression::Index {
+            left,
+            optional: false,
+            ..
+        } => is_update_target(&left.node),
+        _ => false,
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{ast::*, lexer::Lexer, parser::Parser};
+use crate::{
+    ast::*,
+    lexer::{error::LexError, Lexer},
+    parser::{error::ParseError, Parser},
+    printer::format,
+    token::{Position, Span, Token},
+};
 
 fn check_parse_errors(p: Parser) {
     let errors = p.errors;
@@ -10,6 +16,37 @@ fn check_parse_errors(p: Parser) {
     panic!("Parser has {} errors!\n{:?}\n", errors.len(), errors);
 }
 
+/// The span of a token of `len` characters on a single line.
+fn span(offset: usize, line: usize, column: usize, len: usize) -> Span {
+    Span::new(
+        Position::new(offset, line, column),
+        Position::new(offset + len, line, column + len),
+    )
+}
+
+fn statements(program: Program) -> Vec<Statement> {
+    program.statements.into_iter().map(|s| s.node).collect()
+}
+
+fn ident(name: &str) -> Box<Spanned<Expression>> {
+    Box::new(Expression::Ident(Ident(String::from(name))).into())
+}
+
+fn param(name: &str) -> Param {
+    Param {
+        name: Ident(String::from(name)),
+        default: None,
+    }
+}
+
+fn infix(
+    infix: Infix,
+    left: Box<Spanned<Expression>>,
+    right: Box<Spanned<Expression>>,
+) -> Box<Spanned<Expression>> {
+    Box::new(Expression::Infix(infix, left, right).into())
+}
+
 #[test]
 pub fn test_let_statement() {
     let input = r#"
@@ -17,31 +54,150 @@ pub fn test_let_statement() {
                     let y = 5;
                     let foobar = 5;
                 "#;
-    let l = Lexer::new(input.to_string());
-    let mut p = Parser::new(l);
-
-    let program = p.parse_program();
-    check_parse_errors(p);
+    let program = parse(input);
 
     assert_eq!(
         vec![
             Statement::Let(
-                Ident(String::from("x")),
-                Expression::Literal(Literal::Int(5))
+                Pattern::Ident(Ident(String::from("x"))),
+                Expression::Literal(Literal::Int(5)).into()
             ),
             Statement::Let(
-                Ident(String::from("y")),
-                Expression::Literal(Literal::Int(5))
+                Pattern::Ident(Ident(String::from("y"))),
+                Expression::Literal(Literal::Int(5)).into()
             ),
             Statement::Let(
-                Ident(String::from("foobar")),
-                Expression::Literal(Literal::Int(5)),
+                Pattern::Ident(Ident(String::from("foobar"))),
+                Expression::Literal(Literal::Int(5)).into(),
             ),
         ],
-        program.statements,
+        statements(program),
+    );
+}
+
+#[test]
+pub fn test_destructuring_let_statement() {
+    let input = r#"
+        let [a, b, ...rest] = xs;
+        let {"name": n, "age": [_, y]} = person;
+        let {name, age} = person;
+    "#;
+    let program = parse(input);
+
+    let binding = |name: &str| Pattern::Ident(Ident(String::from(name)));
+    let key = |name: &str| Spanned::from(Literal::String(String::from(name)));
+    assert_eq!(
+        statements(program),
+        vec![
+            Statement::Let(
+                Pattern::Array {
+                    items: vec![binding("a"), binding("b")],
+                    rest: Some(Box::new(binding("rest"))),
+                },
+                *ident("xs"),
+            ),
+            Statement::Let(
+                Pattern::Hash(vec![
+                    (key("name"), binding("n")),
+                    (
+                        key("age"),
+                        Pattern::Array {
+                            items: vec![Pattern::Wildcard, binding("y")],
+                            rest: None,
+                        },
+                    ),
+                ]),
+                *ident("person"),
+            ),
+            Statement::Let(
+                Pattern::Hash(vec![
+                    (key("name"), binding("name")),
+                    (key("age"), binding("age"))
+                ]),
+                *ident("person"),
+            ),
+        ]
     );
 }
 
+#[test]
+pub fn test_update_statement() {
+    let int = |i: i64| Expression::Literal(Literal::Int(i)).into();
+    let tests = vec![
+        (
+            "update x = 5;",
+            Statement::Update {
+                target: *ident("x"),
+                operator: None,
+                value: int(5),
+            },
+        ),
+        (
+            "update x += 1;",
+            Statement::Update {
+                target: *ident("x"),
+                operator: Some(Infix::Plus),
+                value: int(1),
+            },
+        ),
+        (
+            "update x %= 2;",
+            Statement::Update {
+                target: *ident("x"),
+                operator: Some(Infix::Modulus),
+                value: int(2),
+            },
+        ),
+        (
+            "update grid[y][x] *= 3;",
+            Statement::Update {
+                target: Expression::Index {
+                    left: Box::new(
+                        Expression::Index {
+                            left: ident("grid"),
+                            index: ident("y"),
+                            optional: false,
+                        }
+                        .into(),
+                    ),
+                    index: ident("x"),
+                    optional: false,
+                }
+                .into(),
+                operator: Some(Infix::Multiply),
+                value: int(3),
+            },
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
+    }
+
+    let tests = vec![
+        (
+            "update f(x) = 1;",
+            "Invalid update target at line 1, column 8",
+        ),
+        (
+            "update x[0] + 1 = 1;",
+            "Invalid update target at line 1, column 8",
+        ),
+        (
+            "update x == 1;",
+            "Invalid update target at line 1, column 8",
+        ),
+        ("update x 1;", "Expected `=`, got `1` at line 1, column 10"),
+    ];
+
+    for (input, expect) in tests {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        parser.parse_program();
+        assert_eq!(expect, parser.errors[0].to_string());
+    }
+}
+
 #[test]
 pub fn test_return_statement() {
     let input = r#"
@@ -50,19 +206,15 @@ pub fn test_return_statement() {
                   return 7894687;
               "#;
 
-    let l = Lexer::new(input.to_string());
-    let mut p = Parser::new(l);
-
-    let program = p.parse_program();
-    check_parse_errors(p);
+    let program = parse(input);
 
     assert_eq!(
         vec![
-            Statement::Return(Expression::Literal(Literal::Int(5))),
-            Statement::Return(Expression::Literal(Literal::Int(10))),
-            Statement::Return(Expression::Literal(Literal::Int(7894687))),
+            Statement::Return(Expression::Literal(Literal::Int(5)).into()),
+            Statement::Return(Expression::Literal(Literal::Int(10)).into()),
+            Statement::Return(Expression::Literal(Literal::Int(7894687)).into()),
         ],
-        program.statements
+        statements(program)
     )
 }
 
@@ -70,16 +222,12 @@ pub fn test_return_statement() {
 pub fn test_ident_expression() {
     let input: String = String::from("foo;");
 
-    let l = Lexer::new(input);
-    let mut p = Parser::new(l);
-
-    let program = p.parse_program();
-    check_parse_errors(p);
+    let program = parse(&input);
     assert_eq!(
-        vec![Statement::Expression(Expression::Ident(Ident(
-            String::from("foo")
-        )))],
-        program.statements
+        vec![Statement::Expression(
+            Expression::Ident(Ident(String::from("foo"))).into()
+        )],
+        statements(program)
     );
 }
 
@@ -87,15 +235,12 @@ pub fn test_ident_expression() {
 pub fn test_string_literal_expression() {
     let input: String = String::from("\"hello world\"");
 
-    let l = Lexer::new(input);
-    let mut p = Parser::new(l);
-    let program = p.parse_program();
-    check_parse_errors(p);
+    let program = parse(&input);
     assert_eq!(
-        vec![Statement::Expression(Expression::Literal(Literal::String(
-            String::from("hello world")
-        )))],
-        program.statements
+        vec![Statement::Expression(
+            Expression::Literal(Literal::String(String::from("hello world"))).into()
+        )],
+        statements(program)
     )
 }
 
@@ -103,14 +248,26 @@ pub fn test_string_literal_expression() {
 pub fn test_int_literal_expression() {
     let input: String = String::from("5;");
 
-    let l = Lexer::new(input);
-    let mut p = Parser::new(l);
+    let program = parse(&input);
+    assert_eq!(
+        vec![Statement::Expression(
+            Expression::Literal(Literal::Int(5)).into()
+        )],
+        statements(program)
+    );
+}
 
-    let program = p.parse_program();
-    check_parse_errors(p);
+#[test]
+pub fn test_float_literal_expression() {
+    let input: String = String::from("3.5; 1e-3;");
+
+    let program = parse(&input);
     assert_eq!(
-        vec![Statement::Expression(Expression::Literal(Literal::Int(5)))],
-        program.statements
+        vec![
+            Statement::Expression(Expression::Literal(Literal::Float(3.5)).into()),
+            Statement::Expression(Expression::Literal(Literal::Float(0.001)).into()),
+        ],
+        statements(program)
     );
 }
 
@@ -121,35 +278,40 @@ pub fn test_bool_literal_expression() {
             false
         "#;
 
-    let l = Lexer::new(input.to_string());
-    let mut p = Parser::new(l);
-
-    let program = p.parse_program();
-    check_parse_errors(p);
+    let program = parse(input);
     assert_eq!(
         vec![
-            Statement::Expression(Expression::Literal(Literal::Bool(true))),
-            Statement::Expression(Expression::Literal(Literal::Bool(false)))
+            Statement::Expression(Expression::Literal(Literal::Bool(true)).into()),
+            Statement::Expression(Expression::Literal(Literal::Bool(false)).into())
         ],
-        program.statements
+        statements(program)
     );
 }
 
 #[test]
 pub fn test_prefix_expression() {
     let tests: Vec<Statement> = vec![
-        Statement::Expression(Expression::Prefix(
-            Prefix::Not,
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Prefix(
-            Prefix::Minus,
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Prefix(
-            Prefix::Plus,
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
+        Statement::Expression(
+            Expression::Prefix(
+                Prefix::Not,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Prefix(
+                Prefix::Minus,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Prefix(
+                Prefix::Plus,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
     ];
 
     let input = r#"
@@ -158,56 +320,77 @@ pub fn test_prefix_expression() {
         +5;
     "#;
 
-    let mut parser = Parser::new(Lexer::new(input.to_string()));
-    let program = parser.parse_program();
-
-    check_parse_errors(parser);
-    assert_eq!(tests, program.statements);
+    let program = parse(input);
+    assert_eq!(tests, statements(program));
 }
 
 #[test]
 pub fn test_infix_expression() {
     let tests: Vec<Statement> = vec![
-        Statement::Expression(Expression::Infix(
-            Infix::Plus,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Infix(
-            Infix::Minus,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Infix(
-            Infix::Multiply,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Infix(
-            Infix::Divide,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Infix(
-            Infix::LessThan,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Infix(
-            Infix::GreaterThan,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Infix(
-            Infix::Equal,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
-        Statement::Expression(Expression::Infix(
-            Infix::NotEqual,
-            Box::new(Expression::Literal(Literal::Int(5))),
-            Box::new(Expression::Literal(Literal::Int(5))),
-        )),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::Plus,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::Minus,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::Multiply,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::Divide,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::LessThan,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::GreaterThan,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::Equal,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
+        Statement::Expression(
+            Expression::Infix(
+                Infix::NotEqual,
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+                Box::new(Expression::Literal(Literal::Int(5)).into()),
+            )
+            .into(),
+        ),
     ];
 
     let input = r#"
@@ -221,11 +404,8 @@ pub fn test_infix_expression() {
         5!=5;
     "#;
 
-    let mut parser = Parser::new(Lexer::new(input.to_string()));
-    let program = parser.parse_program();
-
-    check_parse_errors(parser);
-    assert_eq!(tests, program.statements);
+    let program = parse(input);
+    assert_eq!(tests, statements(program));
 }
 
 #[test]
@@ -233,459 +413,959 @@ fn test_operator_precedence_parsing() {
     let tests = vec![
         (
             "-a * b",
-            Statement::Expression(Expression::Infix(
-                Infix::Multiply,
-                Box::new(Expression::Prefix(
-                    Prefix::Minus,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("b")))),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Multiply,
+                    Box::new(
+                        Expression::Prefix(
+                            Prefix::Minus,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "!-a",
-            Statement::Expression(Expression::Prefix(
-                Prefix::Not,
-                Box::new(Expression::Prefix(
-                    Prefix::Minus,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                )),
-            )),
+            Statement::Expression(
+                Expression::Prefix(
+                    Prefix::Not,
+                    Box::new(
+                        Expression::Prefix(
+                            Prefix::Minus,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                        )
+                        .into(),
+                    ),
+                )
+                .into(),
+            ),
         ),
         (
             "a+b+c",
-            Statement::Expression(Expression::Infix(
-                Infix::Plus,
-                Box::new(Expression::Infix(
+            Statement::Expression(
+                Expression::Infix(
                     Infix::Plus,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("c")))),
-            )),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("c"))).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "a+b-c",
-            Statement::Expression(Expression::Infix(
-                Infix::Minus,
-                Box::new(Expression::Infix(
-                    Infix::Plus,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("c")))),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Minus,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("c"))).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "a*b*c",
-            Statement::Expression(Expression::Infix(
-                Infix::Multiply,
-                Box::new(Expression::Infix(
+            Statement::Expression(
+                Expression::Infix(
                     Infix::Multiply,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("c")))),
-            )),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Multiply,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("c"))).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "a*b/c",
-            Statement::Expression(Expression::Infix(
-                Infix::Divide,
-                Box::new(Expression::Infix(
-                    Infix::Multiply,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("c")))),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Divide,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Multiply,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("c"))).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "a/b+c",
-            Statement::Expression(Expression::Infix(
-                Infix::Plus,
-                Box::new(Expression::Infix(
-                    Infix::Divide,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("c")))),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Plus,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Divide,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("c"))).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "a+b/c",
-            Statement::Expression(Expression::Infix(
-                Infix::Plus,
-                Box::new(Expression::Ident(Ident(String::from("a")))),
-                Box::new(Expression::Infix(
-                    Infix::Divide,
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                    Box::new(Expression::Ident(Ident(String::from("c")))),
-                )),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Plus,
+                    Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Divide,
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("c"))).into()),
+                        )
+                        .into(),
+                    ),
+                )
+                .into(),
+            ),
         ),
         (
             // "(((a + (b * c)) + (d / e)) - f)",
             "a + b * c + d / e - f",
-            Statement::Expression(Expression::Infix(
-                Infix::Minus,
-                Box::new(Expression::Infix(
-                    Infix::Plus,
-                    Box::new(Expression::Infix(
-                        Infix::Plus,
-                        Box::new(Expression::Ident(Ident(String::from("a")))),
-                        Box::new(Expression::Infix(
-                            Infix::Multiply,
-                            Box::new(Expression::Ident(Ident(String::from("b")))),
-                            Box::new(Expression::Ident(Ident(String::from("c")))),
-                        )),
-                    )),
-                    Box::new(Expression::Infix(
-                        Infix::Divide,
-                        Box::new(Expression::Ident(Ident(String::from("d")))),
-                        Box::new(Expression::Ident(Ident(String::from("e")))),
-                    )),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("f")))),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Minus,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Plus,
+                                    Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                                    Box::new(
+                                        Expression::Infix(
+                                            Infix::Multiply,
+                                            Box::new(
+                                                Expression::Ident(Ident(String::from("b"))).into(),
+                                            ),
+                                            Box::new(
+                                                Expression::Ident(Ident(String::from("c"))).into(),
+                                            ),
+                                        )
+                                        .into(),
+                                    ),
+                                )
+                                .into(),
+                            ),
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Divide,
+                                    Box::new(Expression::Ident(Ident(String::from("d"))).into()),
+                                    Box::new(Expression::Ident(Ident(String::from("e"))).into()),
+                                )
+                                .into(),
+                            ),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("f"))).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "5 > 4 == 3 < 4",
-            Statement::Expression(Expression::Infix(
-                Infix::Equal,
-                Box::new(Expression::Infix(
-                    Infix::GreaterThan,
-                    Box::new(Expression::Literal(Literal::Int(5))),
-                    Box::new(Expression::Literal(Literal::Int(4))),
-                )),
-                Box::new(Expression::Infix(
-                    Infix::LessThan,
-                    Box::new(Expression::Literal(Literal::Int(3))),
-                    Box::new(Expression::Literal(Literal::Int(4))),
-                )),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Equal,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThan,
+                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                            Box::new(Expression::Literal(Literal::Int(4)).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::LessThan,
+                            Box::new(Expression::Literal(Literal::Int(3)).into()),
+                            Box::new(Expression::Literal(Literal::Int(4)).into()),
+                        )
+                        .into(),
+                    ),
+                )
+                .into(),
+            ),
         ),
         (
             "5 < 4 != 3 > 4",
-            Statement::Expression(Expression::Infix(
-                Infix::NotEqual,
-                Box::new(Expression::Infix(
-                    Infix::LessThan,
-                    Box::new(Expression::Literal(Literal::Int(5))),
-                    Box::new(Expression::Literal(Literal::Int(4))),
-                )),
-                Box::new(Expression::Infix(
-                    Infix::GreaterThan,
-                    Box::new(Expression::Literal(Literal::Int(3))),
-                    Box::new(Expression::Literal(Literal::Int(4))),
-                )),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::NotEqual,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::LessThan,
+                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                            Box::new(Expression::Literal(Literal::Int(4)).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThan,
+                            Box::new(Expression::Literal(Literal::Int(3)).into()),
+                            Box::new(Expression::Literal(Literal::Int(4)).into()),
+                        )
+                        .into(),
+                    ),
+                )
+                .into(),
+            ),
         ),
         (
             "5 >= 4 == 3 <= 4",
-            Statement::Expression(Expression::Infix(
-                Infix::Equal,
-                Box::new(Expression::Infix(
-                    Infix::GreaterThanEqual,
-                    Box::new(Expression::Literal(Literal::Int(5))),
-                    Box::new(Expression::Literal(Literal::Int(4))),
-                )),
-                Box::new(Expression::Infix(
-                    Infix::LessThanEqual,
-                    Box::new(Expression::Literal(Literal::Int(3))),
-                    Box::new(Expression::Literal(Literal::Int(4))),
-                )),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Equal,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThanEqual,
+                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                            Box::new(Expression::Literal(Literal::Int(4)).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::LessThanEqual,
+                            Box::new(Expression::Literal(Literal::Int(3)).into()),
+                            Box::new(Expression::Literal(Literal::Int(4)).into()),
+                        )
+                        .into(),
+                    ),
+                )
+                .into(),
+            ),
         ),
         (
             "3 + 4 * 5 == 3 * 1 + 4 * 5",
-            Statement::Expression(Expression::Infix(
-                Infix::Equal,
-                Box::new(Expression::Infix(
-                    Infix::Plus,
-                    Box::new(Expression::Literal(Literal::Int(3))),
-                    Box::new(Expression::Infix(
-                        Infix::Multiply,
-                        Box::new(Expression::Literal(Literal::Int(4))),
-                        Box::new(Expression::Literal(Literal::Int(5))),
-                    )),
-                )),
-                Box::new(Expression::Infix(
-                    Infix::Plus,
-                    Box::new(Expression::Infix(
-                        Infix::Multiply,
-                        Box::new(Expression::Literal(Literal::Int(3))),
-                        Box::new(Expression::Literal(Literal::Int(1))),
-                    )),
-                    Box::new(Expression::Infix(
-                        Infix::Multiply,
-                        Box::new(Expression::Literal(Literal::Int(4))),
-                        Box::new(Expression::Literal(Literal::Int(5))),
-                    )),
-                )),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Equal,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Literal(Literal::Int(3)).into()),
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Multiply,
+                                    Box::new(Expression::Literal(Literal::Int(4)).into()),
+                                    Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                )
+                                .into(),
+                            ),
+                        )
+                        .into(),
+                    ),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Multiply,
+                                    Box::new(Expression::Literal(Literal::Int(3)).into()),
+                                    Box::new(Expression::Literal(Literal::Int(1)).into()),
+                                )
+                                .into(),
+                            ),
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Multiply,
+                                    Box::new(Expression::Literal(Literal::Int(4)).into()),
+                                    Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                )
+                                .into(),
+                            ),
+                        )
+                        .into(),
+                    ),
+                )
+                .into(),
+            ),
         ),
         (
             "true",
-            Statement::Expression(Expression::Literal(Literal::Bool(true))),
+            Statement::Expression(Expression::Literal(Literal::Bool(true)).into()),
         ),
         (
             "false",
-            Statement::Expression(Expression::Literal(Literal::Bool(false))),
+            Statement::Expression(Expression::Literal(Literal::Bool(false)).into()),
         ),
         (
             "3 > 5 == false",
-            Statement::Expression(Expression::Infix(
-                Infix::Equal,
-                Box::new(Expression::Infix(
-                    Infix::GreaterThan,
-                    Box::new(Expression::Literal(Literal::Int(3))),
-                    Box::new(Expression::Literal(Literal::Int(5))),
-                )),
-                Box::new(Expression::Literal(Literal::Bool(false))),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Equal,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThan,
+                            Box::new(Expression::Literal(Literal::Int(3)).into()),
+                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Literal(Literal::Bool(false)).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "3 < 5 == true",
-            Statement::Expression(Expression::Infix(
-                Infix::Equal,
-                Box::new(Expression::Infix(
-                    Infix::LessThan,
-                    Box::new(Expression::Literal(Literal::Int(3))),
-                    Box::new(Expression::Literal(Literal::Int(5))),
-                )),
-                Box::new(Expression::Literal(Literal::Bool(true))),
-            )),
+            Statement::Expression(
+                Expression::Infix(
+                    Infix::Equal,
+                    Box::new(
+                        Expression::Infix(
+                            Infix::LessThan,
+                            Box::new(Expression::Literal(Literal::Int(3)).into()),
+                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Literal(Literal::Bool(true)).into()),
+                )
+                .into(),
+            ),
         ),
         (
             "a + (b + c)",
-            Statement::Expression(Expression::Infix(
-                Infix::Plus,
-                Box::new(Expression::Ident(Ident(String::from("a")))),
-                Box::new(Expression::Infix(
+            Statement::Expression(
+                Expression::Infix(
                     Infix::Plus,
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                    Box::new(Expression::Ident(Ident(String::from("c")))),
-                )),
-            )),
+                    Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                    Box::new(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("c"))).into()),
+                        )
+                        .into(),
+                    ),
+                )
+                .into(),
+            ),
         ),
         (
             "a * [1, 2, 3, 4][b * c] * d",
-            Statement::Expression(Expression::Infix(
-                Infix::Multiply,
-                Box::new(Expression::Infix(
+            Statement::Expression(
+                Expression::Infix(
                     Infix::Multiply,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                    Box::new(Expression::Index {
-                        left: Box::new(Expression::Literal(Literal::Array(vec![
-                            Expression::Literal(Literal::Int(1)),
-                            Expression::Literal(Literal::Int(2)),
-                            Expression::Literal(Literal::Int(3)),
-                            Expression::Literal(Literal::Int(4)),
-                        ]))),
-                        index: Box::new(Expression::Infix(
+                    Box::new(
+                        Expression::Infix(
                             Infix::Multiply,
-                            Box::new(Expression::Ident(Ident(String::from("b")))),
-                            Box::new(Expression::Ident(Ident(String::from("c")))),
-                        )),
-                    }),
-                )),
-                Box::new(Expression::Ident(Ident(String::from("d")))),
-            )),
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(
+                                Expression::Index {
+                                    left: Box::new(
+                                        Expression::Literal(Literal::Array(vec![
+                                            Expression::Literal(Literal::Int(1)).into(),
+                                            Expression::Literal(Literal::Int(2)).into(),
+                                            Expression::Literal(Literal::Int(3)).into(),
+                                            Expression::Literal(Literal::Int(4)).into(),
+                                        ]))
+                                        .into(),
+                                    ),
+                                    index: Box::new(
+                                        Expression::Infix(
+                                            Infix::Multiply,
+                                            Box::new(
+                                                Expression::Ident(Ident(String::from("b"))).into(),
+                                            ),
+                                            Box::new(
+                                                Expression::Ident(Ident(String::from("c"))).into(),
+                                            ),
+                                        )
+                                        .into(),
+                                    ),
+                                    optional: false,
+                                }
+                                .into(),
+                            ),
+                        )
+                        .into(),
+                    ),
+                    Box::new(Expression::Ident(Ident(String::from("d"))).into()),
+                )
+                .into(),
+            ),
         ),
     ];
 
     for (input, expect) in tests {
-        let mut parser = Parser::new(Lexer::new(input.to_string()));
-        let program = parser.parse_program();
-
-        check_parse_errors(parser);
-        assert_eq!(vec![expect], program.statements);
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
     }
 }
 
 #[test]
-pub fn test_if_expression() {
-    let tests: Vec<(&str, Statement)> = vec![
+pub fn test_logical_operator_precedence() {
+    let tests = vec![
         (
-            "if ( x > y ) { x }",
-            Statement::Expression(Expression::If {
-                condition: Box::new(Expression::Infix(
-                    Infix::GreaterThan,
-                    Box::new(Expression::Ident(Ident(String::from("x")))),
-                    Box::new(Expression::Ident(Ident(String::from("y")))),
-                )),
-                consequence: vec![Statement::Expression(Expression::Ident(Ident(
-                    String::from("x"),
-                )))],
-                alternative: None,
-            }),
+            "a || b && c == d",
+            infix(
+                Infix::Or,
+                ident("a"),
+                infix(
+                    Infix::And,
+                    ident("b"),
+                    infix(Infix::Equal, ident("c"), ident("d")),
+                ),
+            ),
         ),
         (
-            "if ((5 * 5) + 5 >= 30) { true }",
-            Statement::Expression(Expression::If {
-                condition: Box::new(Expression::Infix(
-                    Infix::GreaterThanEqual,
-                    Box::new(Expression::Infix(
-                        Infix::Plus,
-                        Box::new(Expression::Infix(
-                            Infix::Multiply,
-                            Box::new(Expression::Literal(Literal::Int(5))),
-                            Box::new(Expression::Literal(Literal::Int(5))),
-                        )),
-                        Box::new(Expression::Literal(Literal::Int(5))),
-                    )),
-                    Box::new(Expression::Literal(Literal::Int(30))),
-                )),
-                consequence: vec![Statement::Expression(Expression::Literal(Literal::Bool(
-                    true,
-                )))],
-                alternative: None,
-            }),
+            "a && b || c",
+            infix(
+                Infix::Or,
+                infix(Infix::And, ident("a"), ident("b")),
+                ident("c"),
+            ),
+        ),
+        (
+            "a < 1 && !b",
+            infix(
+                Infix::And,
+                infix(
+                    Infix::LessThan,
+                    ident("a"),
+                    Box::new(Expression::Literal(Literal::Int(1)).into()),
+                ),
+                Box::new(Expression::Prefix(Prefix::Not, ident("b")).into()),
+            ),
         ),
     ];
 
     for (input, expect) in tests {
-        let mut parser = Parser::new(Lexer::new(input.to_string()));
-        let program = parser.parse_program();
-
-        check_parse_errors(parser);
-        assert_eq!(vec![expect], program.statements);
+        let program = parse(input);
+        assert_eq!(vec![Statement::Expression(*expect)], statements(program));
     }
 }
 
 #[test]
-pub fn test_if_else_expression() {
-    let tests: Vec<(&str, Statement)> = vec![
+pub fn test_bitwise_operator_precedence() {
+    let int = |i: i64| Box::new(Expression::Literal(Literal::Int(i)).into());
+    let tests = vec![
         (
-            "if ( x > y ) { x } else { y }",
-            Statement::Expression(Expression::If {
-                condition: Box::new(Expression::Infix(
-                    Infix::GreaterThan,
-                    Box::new(Expression::Ident(Ident(String::from("x")))),
-                    Box::new(Expression::Ident(Ident(String::from("y")))),
-                )),
-                consequence: vec![Statement::Expression(Expression::Ident(Ident(
-                    String::from("x"),
-                )))],
-                alternative: Some(vec![Statement::Expression(Expression::Ident(Ident(
-                    String::from("y"),
-                )))]),
-            }),
+            "a | b ^ c & d",
+            infix(
+                Infix::BitOr,
+                ident("a"),
+                infix(
+                    Infix::BitXor,
+                    ident("b"),
+                    infix(Infix::BitAnd, ident("c"), ident("d")),
+                ),
+            ),
         ),
         (
-            "if ( x > y ) { x } else if (y == x) { 1 } else { y }",
-            Statement::Expression(Expression::If {
-                condition: Box::new(Expression::Infix(
-                    Infix::GreaterThan,
-                    Box::new(Expression::Ident(Ident(String::from("x")))),
-                    Box::new(Expression::Ident(Ident(String::from("y")))),
-                )),
-                consequence: vec![Statement::Expression(Expression::Ident(Ident(
-                    String::from("x"),
-                )))],
-                alternative: Some(vec![Statement::Expression(Expression::If {
-                    condition: Box::new(Expression::Infix(
-                        Infix::Equal,
-                        Box::new(Expression::Ident(Ident(String::from("y")))),
-                        Box::new(Expression::Ident(Ident(String::from("x")))),
-                    )),
-                    consequence: vec![Statement::Expression(Expression::Literal(Literal::Int(1)))],
-                    alternative: Some(vec![Statement::Expression(Expression::Ident(Ident(
-                        String::from("y"),
-                    )))]),
-                })]),
-            }),
+            "a & 1 == 0",
+            infix(
+                Infix::Equal,
+                infix(Infix::BitAnd, ident("a"), int(1)),
+                int(0),
+            ),
         ),
         (
-            r#"if ( x > y ) { x } 
-               else if (y == x) { 1 } 
-               else if (y * 2 > x ) { 2 } 
-               else { y }"#,
-            Statement::Expression(Expression::If {
-                condition: Box::new(Expression::Infix(
-                    Infix::GreaterThan,
-                    Box::new(Expression::Ident(Ident(String::from("x")))),
-                    Box::new(Expression::Ident(Ident(String::from("y")))),
-                )),
-                consequence: vec![Statement::Expression(Expression::Ident(Ident(
-                    String::from("x"),
-                )))],
-                alternative: Some(vec![Statement::Expression(Expression::If {
-                    condition: Box::new(Expression::Infix(
-                        Infix::Equal,
-                        Box::new(Expression::Ident(Ident(String::from("y")))),
-                        Box::new(Expression::Ident(Ident(String::from("x")))),
-                    )),
-                    consequence: vec![Statement::Expression(Expression::Literal(Literal::Int(1)))],
-                    alternative: Some(vec![Statement::Expression(Expression::If {
-                        condition: Box::new(Expression::Infix(
-                            Infix::GreaterThan,
-                            Box::new(Expression::Infix(
-                                Infix::Multiply,
-                                Box::new(Expression::Ident(Ident(String::from("y")))),
-                                Box::new(Expression::Literal(Literal::Int(2))),
-                            )),
-                            Box::new(Expression::Ident(Ident(String::from("x")))),
-                        )),
-                        consequence: vec![Statement::Expression(Expression::Literal(
-                            Literal::Int(2),
-                        ))],
-                        alternative: Some(vec![Statement::Expression(Expression::Ident(Ident(
-                            String::from("y"),
-                        )))]),
-                    })]),
-                })]),
-            }),
+            "1 << a + 2",
+            infix(
+                Infix::ShiftLeft,
+                int(1),
+                infix(Infix::Plus, ident("a"), int(2)),
+            ),
         ),
         (
-            r#"
-             if ((5 * 5) + 5 >= 30) { 
-                true
-             } else {
+            "a ** b ** 2",
+            infix(
+                Infix::Power,
+                ident("a"),
+                infix(Infix::Power, ident("b"), int(2)),
+            ),
+        ),
+        (
+            "-a ** 2 * b",
+            infix(
+                Infix::Multiply,
+                Box::new(
+                    Expression::Prefix(Prefix::Minus, infix(Infix::Power, ident("a"), int(2)))
+                        .into(),
+                ),
+                ident("b"),
+            ),
+        ),
+        (
+            "~a >> 1",
+            infix(
+                Infix::ShiftRight,
+                Box::new(Expression::Prefix(Prefix::BitNot, ident("a")).into()),
+                int(1),
+            ),
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(vec![Statement::Expression(*expect)], statements(program));
+    }
+}
+
+#[test]
+pub fn test_if_expression() {
+    let tests: Vec<(&str, Statement)> = vec![
+        (
+            "if ( x > y ) { x }",
+            Statement::Expression(
+                Expression::If {
+                    condition: Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThan,
+                            Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("y"))).into()),
+                        )
+                        .into(),
+                    ),
+                    consequence: vec![Statement::Expression(
+                        Expression::Ident(Ident(String::from("x"))).into(),
+                    )
+                    .into()],
+                    alternative: None,
+                }
+                .into(),
+            ),
+        ),
+        (
+            "if ((5 * 5) + 5 >= 30) { true }",
+            Statement::Expression(
+                Expression::If {
+                    condition: Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThanEqual,
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Plus,
+                                    Box::new(
+                                        Expression::Infix(
+                                            Infix::Multiply,
+                                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                        )
+                                        .into(),
+                                    ),
+                                    Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                )
+                                .into(),
+                            ),
+                            Box::new(Expression::Literal(Literal::Int(30)).into()),
+                        )
+                        .into(),
+                    ),
+                    consequence: vec![Statement::Expression(
+                        Expression::Literal(Literal::Bool(true)).into(),
+                    )
+                    .into()],
+                    alternative: None,
+                }
+                .into(),
+            ),
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
+    }
+}
+
+#[test]
+pub fn test_if_else_expression() {
+    let tests: Vec<(&str, Statement)> = vec![
+        (
+            "if ( x > y ) { x } else { y }",
+            Statement::Expression(
+                Expression::If {
+                    condition: Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThan,
+                            Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("y"))).into()),
+                        )
+                        .into(),
+                    ),
+                    consequence: vec![Statement::Expression(
+                        Expression::Ident(Ident(String::from("x"))).into(),
+                    )
+                    .into()],
+                    alternative: Some(vec![Statement::Expression(
+                        Expression::Ident(Ident(String::from("y"))).into(),
+                    )
+                    .into()]),
+                }
+                .into(),
+            ),
+        ),
+        (
+            "if ( x > y ) { x } else if (y == x) { 1 } else { y }",
+            Statement::Expression(
+                Expression::If {
+                    condition: Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThan,
+                            Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("y"))).into()),
+                        )
+                        .into(),
+                    ),
+                    consequence: vec![Statement::Expression(
+                        Expression::Ident(Ident(String::from("x"))).into(),
+                    )
+                    .into()],
+                    alternative: Some(vec![Statement::Expression(
+                        Expression::If {
+                            condition: Box::new(
+                                Expression::Infix(
+                                    Infix::Equal,
+                                    Box::new(Expression::Ident(Ident(String::from("y"))).into()),
+                                    Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                                )
+                                .into(),
+                            ),
+                            consequence: vec![Statement::Expression(
+                                Expression::Literal(Literal::Int(1)).into(),
+                            )
+                            .into()],
+                            alternative: Some(vec![Statement::Expression(
+                                Expression::Ident(Ident(String::from("y"))).into(),
+                            )
+                            .into()]),
+                        }
+                        .into(),
+                    )
+                    .into()]),
+                }
+                .into(),
+            ),
+        ),
+        (
+            r#"if ( x > y ) { x } 
+               else if (y == x) { 1 } 
+               else if (y * 2 > x ) { 2 } 
+               else { y }"#,
+            Statement::Expression(
+                Expression::If {
+                    condition: Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThan,
+                            Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("y"))).into()),
+                        )
+                        .into(),
+                    ),
+                    consequence: vec![Statement::Expression(
+                        Expression::Ident(Ident(String::from("x"))).into(),
+                    )
+                    .into()],
+                    alternative: Some(vec![Statement::Expression(
+                        Expression::If {
+                            condition: Box::new(
+                                Expression::Infix(
+                                    Infix::Equal,
+                                    Box::new(Expression::Ident(Ident(String::from("y"))).into()),
+                                    Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                                )
+                                .into(),
+                            ),
+                            consequence: vec![Statement::Expression(
+                                Expression::Literal(Literal::Int(1)).into(),
+                            )
+                            .into()],
+                            alternative: Some(vec![Statement::Expression(
+                                Expression::If {
+                                    condition: Box::new(
+                                        Expression::Infix(
+                                            Infix::GreaterThan,
+                                            Box::new(
+                                                Expression::Infix(
+                                                    Infix::Multiply,
+                                                    Box::new(
+                                                        Expression::Ident(Ident(String::from("y")))
+                                                            .into(),
+                                                    ),
+                                                    Box::new(
+                                                        Expression::Literal(Literal::Int(2)).into(),
+                                                    ),
+                                                )
+                                                .into(),
+                                            ),
+                                            Box::new(
+                                                Expression::Ident(Ident(String::from("x"))).into(),
+                                            ),
+                                        )
+                                        .into(),
+                                    ),
+                                    consequence: vec![Statement::Expression(
+                                        Expression::Literal(Literal::Int(2)).into(),
+                                    )
+                                    .into()],
+                                    alternative: Some(vec![Statement::Expression(
+                                        Expression::Ident(Ident(String::from("y"))).into(),
+                                    )
+                                    .into()]),
+                                }
+                                .into(),
+                            )
+                            .into()]),
+                        }
+                        .into(),
+                    )
+                    .into()]),
+                }
+                .into(),
+            ),
+        ),
+        (
+            r#"
+             if ((5 * 5) + 5 >= 30) { 
+                true
+             } else {
                 return 5 + (89 * 64 / 10);
              }
             "#,
-            Statement::Expression(Expression::If {
-                condition: Box::new(Expression::Infix(
-                    Infix::GreaterThanEqual,
-                    Box::new(Expression::Infix(
-                        Infix::Plus,
-                        Box::new(Expression::Infix(
-                            Infix::Multiply,
-                            Box::new(Expression::Literal(Literal::Int(5))),
-                            Box::new(Expression::Literal(Literal::Int(5))),
-                        )),
-                        Box::new(Expression::Literal(Literal::Int(5))),
-                    )),
-                    Box::new(Expression::Literal(Literal::Int(30))),
-                )),
-                consequence: vec![Statement::Expression(Expression::Literal(Literal::Bool(
-                    true,
-                )))],
-                alternative: Some(vec![Statement::Return(Expression::Infix(
-                    Infix::Plus,
-                    Box::new(Expression::Literal(Literal::Int(5))),
-                    Box::new(Expression::Infix(
-                        Infix::Divide,
-                        Box::new(Expression::Infix(
-                            Infix::Multiply,
-                            Box::new(Expression::Literal(Literal::Int(89))),
-                            Box::new(Expression::Literal(Literal::Int(64))),
-                        )),
-                        Box::new(Expression::Literal(Literal::Int(10))),
-                    )),
-                ))]),
-            }),
+            Statement::Expression(
+                Expression::If {
+                    condition: Box::new(
+                        Expression::Infix(
+                            Infix::GreaterThanEqual,
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Plus,
+                                    Box::new(
+                                        Expression::Infix(
+                                            Infix::Multiply,
+                                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                        )
+                                        .into(),
+                                    ),
+                                    Box::new(Expression::Literal(Literal::Int(5)).into()),
+                                )
+                                .into(),
+                            ),
+                            Box::new(Expression::Literal(Literal::Int(30)).into()),
+                        )
+                        .into(),
+                    ),
+                    consequence: vec![Statement::Expression(
+                        Expression::Literal(Literal::Bool(true)).into(),
+                    )
+                    .into()],
+                    alternative: Some(vec![Statement::Return(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                            Box::new(
+                                Expression::Infix(
+                                    Infix::Divide,
+                                    Box::new(
+                                        Expression::Infix(
+                                            Infix::Multiply,
+                                            Box::new(Expression::Literal(Literal::Int(89)).into()),
+                                            Box::new(Expression::Literal(Literal::Int(64)).into()),
+                                        )
+                                        .into(),
+                                    ),
+                                    Box::new(Expression::Literal(Literal::Int(10)).into()),
+                                )
+                                .into(),
+                            ),
+                        )
+                        .into(),
+                    )
+                    .into()]),
+                }
+                .into(),
+            ),
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
+    }
+}
+
+#[test]
+pub fn test_loop_statements() {
+    let input = "while (x < 10) { break; } for (item in items) { continue; }";
+    let program = parse(input);
+
+    assert_eq!(
+        vec![
+            Statement::While {
+                condition: *infix(
+                    Infix::LessThan,
+                    ident("x"),
+                    Box::new(Expression::Literal(Literal::Int(10)).into()),
+                ),
+                body: vec![Statement::Break.into()],
+            },
+            Statement::For {
+                ident: Ident(String::from("item")),
+                iterable: *ident("items"),
+                body: vec![Statement::Continue.into()],
+            },
+        ],
+        statements(program)
+    );
+}
+
+#[test]
+pub fn test_loop_statement_errors() {
+    let tests = vec![
+        ("break;", "`break` outside of a loop at line 1, column 1"),
+        (
+            "while (true) { let f = fn() { continue; }; }",
+            "`continue` outside of a loop at line 1, column 31",
+        ),
+        (
+            "for (1 in xs) {}",
+            "Expected an identifier, got `1` at line 1, column 6",
+        ),
+        (
+            "for (x of xs) {}",
+            "Expected `in`, got `of` at line 1, column 8",
         ),
     ];
 
     for (input, expect) in tests {
         let mut parser = Parser::new(Lexer::new(input.to_string()));
-        let program = parser.parse_program();
+        parser.parse_program();
+        assert_eq!(expect, parser.errors[0].to_string());
+    }
+}
+
+#[test]
+pub fn test_match_expression() {
+    let input = r#"
+        match (x) {
+            0 => a,
+            -1 => b,
+            [first, ...rest] => { first }
+            [_, ...] => c,
+            {"type": t} if t > 1 => d,
+            _ => e,
+        }
+    "#;
+    let program = parse(input);
+
+    let arm = |pattern, guard: Option<Box<Spanned<Expression>>>, body: &str| {
+        Spanned::from(MatchArm {
+            pattern,
+            guard: guard.map(|guard| *guard),
+            body: vec![Statement::Expression(*ident(body)).into()],
+        })
+    };
+    let binding = |name: &str| Pattern::Ident(Ident(String::from(name)));
+    assert_eq!(
+        statements(program),
+        vec![Statement::Expression(
+            Expression::Match {
+                subject: ident("x"),
+                arms: vec![
+                    arm(Pattern::Literal(Literal::Int(0).into()), None, "a"),
+                    arm(Pattern::Literal(Literal::Int(-1).into()), None, "b"),
+                    arm(
+                        Pattern::Array {
+                            items: vec![binding("first")],
+                            rest: Some(Box::new(binding("rest"))),
+                        },
+                        None,
+                        "first"
+                    ),
+                    arm(
+                        Pattern::Array {
+                            items: vec![Pattern::Wildcard],
+                            rest: Some(Box::new(Pattern::Wildcard)),
+                        },
+                        None,
+                        "c"
+                    ),
+                    arm(
+                        Pattern::Hash(vec![(
+                            Literal::String(String::from("type")).into(),
+                            binding("t"),
+                        )]),
+                        Some(infix(
+                            Infix::GreaterThan,
+                            ident("t"),
+                            Box::new(Expression::Literal(Literal::Int(1)).into())
+                        )),
+                        "d"
+                    ),
+                    arm(Pattern::Wildcard, None, "e"),
+                ],
+            }
+            .into()
+        )]
+    );
+}
+
+#[test]
+pub fn test_match_expression_errors() {
+    let tests = vec![
+        (
+            "match (x) { a + 1 => 2 }",
+            "Expected `=>`, got `+` at line 1, column 15",
+        ),
+        (
+            "match (x) { (1) => 2 }",
+            "Expected a pattern, got `(` at line 1, column 13",
+        ),
+        (
+            "match (x) { {a: 1} => 2 }",
+            "Expected a pattern, got `a` at line 1, column 14",
+        ),
+        (
+            "match (x) { 1 => 2 3 => 4 }",
+            "Expected `}` to match `{` at line 1, column 11, got `3` at line 1, column 20",
+        ),
+    ];
 
-        check_parse_errors(parser);
-        assert_eq!(vec![expect], program.statements);
+    for (input, expected) in tests {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        parser.parse_program();
+        assert_eq!(parser.errors[0].to_string(), expected, "{}", input);
     }
 }
 
@@ -696,91 +1376,467 @@ pub fn test_fn_expression() {
         fn(a,b) { return a+b; };
         let sub = fn (a,b) { a-b };
     "#;
-    let mut parser = Parser::new(Lexer::new(input.to_string()));
-    let program = parser.parse_program();
-
-    check_parse_errors(parser);
+    let program = parse(input);
     assert_eq!(
-        program.statements,
+        statements(program),
         vec![
-            Statement::Expression(Expression::Fn {
-                params: vec![],
-                body: vec![Statement::Return(Expression::Literal(Literal::Bool(true)))],
-            }),
-            Statement::Expression(Expression::Fn {
-                params: vec![Ident(String::from("a")), Ident(String::from("b"))],
-                body: vec![Statement::Return(Expression::Infix(
-                    Infix::Plus,
-                    Box::new(Expression::Ident(Ident(String::from("a")))),
-                    Box::new(Expression::Ident(Ident(String::from("b")))),
-                )),]
-            }),
+            Statement::Expression(
+                Expression::Fn {
+                    params: vec![],
+                    rest: None,
+                    body: vec![
+                        Statement::Return(Expression::Literal(Literal::Bool(true)).into()).into()
+                    ],
+                }
+                .into()
+            ),
+            Statement::Expression(
+                Expression::Fn {
+                    params: vec![param("a"), param("b")],
+                    rest: None,
+                    body: vec![Statement::Return(
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                        )
+                        .into()
+                    )
+                    .into(),]
+                }
+                .into()
+            ),
             Statement::Let(
-                Ident(String::from("sub")),
+                Pattern::Ident(Ident(String::from("sub"))),
                 Expression::Fn {
-                    params: vec![Ident(String::from("a")), Ident(String::from("b"))],
-                    body: vec![Statement::Expression(Expression::Infix(
-                        Infix::Minus,
-                        Box::new(Expression::Ident(Ident(String::from("a")))),
-                        Box::new(Expression::Ident(Ident(String::from("b")))),
-                    ))]
+                    params: vec![param("a"), param("b")],
+                    rest: None,
+                    body: vec![Statement::Expression(
+                        Expression::Infix(
+                            Infix::Minus,
+                            Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                            Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                        )
+                        .into()
+                    )
+                    .into()]
                 }
+                .into()
             )
         ]
     );
 }
 
 #[test]
-pub fn test_call_expression() {
-    let tests: Vec<(&str, Statement)> = vec![(
-        "add(1, 3+4)",
-        Statement::Expression(Expression::Call {
-            function: Box::new(Expression::Ident(Ident(String::from("add")))),
-            args: vec![
-                Expression::Literal(Literal::Int(1)),
-                Expression::Infix(
-                    Infix::Plus,
-                    Box::new(Expression::Literal(Literal::Int(3))),
-                    Box::new(Expression::Literal(Literal::Int(4))),
-                ),
-            ],
-        }),
-    )];
+pub fn test_fn_parameters() {
+    let input = "fn(a, b = 2, ...rest) { a }";
+    let program = parse(input);
+    assert_eq!(
+        statements(program),
+        vec![Statement::Expression(
+            Expression::Fn {
+                params: vec![
+                    param("a"),
+                    Param {
+                        name: Ident(String::from("b")),
+                        default: Some(Expression::Literal(Literal::Int(2)).into()),
+                    },
+                ],
+                rest: Some(Ident(String::from("rest"))),
+                body: vec![Statement::Expression(
+                    Expression::Ident(Ident(String::from("a"))).into()
+                )
+                .into()],
+            }
+            .into()
+        )]
+    );
+}
 
-    for (input, expect) in tests {
+#[test]
+pub fn test_fn_statement() {
+    let input = "fn add(a, b) { a + b } fn(x) { x };";
+    let program = parse(input);
+    assert_eq!(
+        statements(program),
+        vec![
+            Statement::Fn {
+                name: Ident(String::from("add")),
+                params: vec![param("a"), param("b")],
+                rest: None,
+                body: vec![Statement::Expression(
+                    Expression::Infix(
+                        Infix::Plus,
+                        Box::new(Expression::Ident(Ident(String::from("a"))).into()),
+                        Box::new(Expression::Ident(Ident(String::from("b"))).into()),
+                    )
+                    .into()
+                )
+                .into()],
+            },
+            Statement::Expression(
+                Expression::Fn {
+                    params: vec![param("x")],
+                    rest: None,
+                    body: vec![Statement::Expression(
+                        Expression::Ident(Ident(String::from("x"))).into()
+                    )
+                    .into()],
+                }
+                .into()
+            ),
+        ]
+    );
+}
+
+#[test]
+pub fn test_fn_parameter_errors() {
+    let tests = vec![
+        (
+            "fn(...1) {}",
+            "Expected function parameter to be an identifier, got `1` at line 1, column 7",
+        ),
+        (
+            "fn(...rest, a) {}",
+            "Expected `)` to match `(` at line 1, column 3, got `,` at line 1, column 11",
+        ),
+        ("fn f {}", "Expected `(`, got `{` at line 1, column 6"),
+        (
+            "f(a: 1, 2)",
+            "Expected a keyword argument after keyword arguments at line 1, column 9",
+        ),
+    ];
+
+    for (input, expected) in tests {
         let mut parser = Parser::new(Lexer::new(input.to_string()));
-        let program = parser.parse_program();
+        parser.parse_program();
+        assert_eq!(parser.errors[0].to_string(), expected, "{}", input);
+    }
+}
+
+#[test]
+pub fn test_pipe_expression() {
+    let ident = |name: &str| Box::new(Expression::Ident(Ident(String::from(name))).into());
+    let tests = vec![
+        (
+            "xs |> f(a) |> g",
+            Expression::Infix(
+                Infix::Pipe,
+                Box::new(
+                    Expression::Infix(
+                        Infix::Pipe,
+                        ident("xs"),
+                        Box::new(
+                            Expression::Call {
+                                function: ident("f"),
+                                args: vec![*ident("a")],
+                                kwargs: vec![],
+                                optional: false,
+                            }
+                            .into(),
+                        ),
+                    )
+                    .into(),
+                ),
+                ident("g"),
+            ),
+        ),
+        (
+            "a || b |> f",
+            Expression::Infix(
+                Infix::Pipe,
+                Box::new(Expression::Infix(Infix::Or, ident("a"), ident("b")).into()),
+                ident("f"),
+            ),
+        ),
+    ];
 
-        check_parse_errors(parser);
-        assert_eq!(vec![expect], program.statements);
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(
+            vec![Statement::Expression(expect.into())],
+            statements(program)
+        );
     }
 }
 
 #[test]
-pub fn test_array_literal() {
-    let tests: Vec<(&str, Statement)> = vec![(
-        "[1, 2 * 2, 3+3]",
-        Statement::Expression(Expression::Literal(Literal::Array(vec![
-            Expression::Literal(Literal::Int(1)),
+pub fn test_optional_expression() {
+    let ident = |name: &str| Box::new(Expression::Ident(Ident(String::from(name))).into());
+    let tests = vec![
+        (
+            "h?[\"a\"] ?? null",
             Expression::Infix(
-                Infix::Multiply,
-                Box::new(Expression::Literal(Literal::Int(2))),
-                Box::new(Expression::Literal(Literal::Int(2))),
+                Infix::Coalesce,
+                Box::new(
+                    Expression::Index {
+                        left: ident("h"),
+                        index: Box::new(
+                            Expression::Literal(Literal::String(String::from("a"))).into(),
+                        ),
+                        optional: true,
+                    }
+                    .into(),
+                ),
+                Box::new(Expression::Literal(Literal::Null).into()),
             ),
+        ),
+        (
+            "f?.(x)",
+            Expression::Call {
+                function: ident("f"),
+                args: vec![*ident("x")],
+                kwargs: vec![],
+                optional: true,
+            },
+        ),
+        (
+            "a ?? b || c",
             Expression::Infix(
-                Infix::Plus,
-                Box::new(Expression::Literal(Literal::Int(3))),
-                Box::new(Expression::Literal(Literal::Int(3))),
+                Infix::Coalesce,
+                ident("a"),
+                Box::new(Expression::Infix(Infix::Or, ident("b"), ident("c")).into()),
             ),
-        ]))),
-    )];
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(
+            vec![Statement::Expression(expect.into())],
+            statements(program)
+        );
+    }
+
+    let tests = vec![
+        ("f?.x", "Expected `(`, got `x` at line 1, column 4"),
+        (
+            "update h?[0] = 1;",
+            "Invalid update target at line 1, column 8",
+        ),
+    ];
+    for (input, expected) in tests {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        parser.parse_program();
+        assert_eq!(parser.errors[0].to_string(), expected, "{}", input);
+    }
+}
+
+#[test]
+pub fn test_slice_expression() {
+    let ident = |name: &str| Box::new(Expression::Ident(Ident(String::from(name))).into());
+    let int = |i: i64| Box::new(Expression::Literal(Literal::Int(i)).into());
+    let tests = vec![
+        (
+            "xs[1:-1]",
+            Expression::Slice {
+                left: ident("xs"),
+                start: Some(int(1)),
+                end: Some(Box::new(Expression::Prefix(Prefix::Minus, int(1)).into())),
+                step: None,
+                optional: false,
+            },
+        ),
+        (
+            "xs[::n]",
+            Expression::Slice {
+                left: ident("xs"),
+                start: None,
+                end: None,
+                step: Some(ident("n")),
+                optional: false,
+            },
+        ),
+        (
+            "xs[a:]",
+            Expression::Slice {
+                left: ident("xs"),
+                start: Some(ident("a")),
+                end: None,
+                step: None,
+                optional: false,
+            },
+        ),
+    ];
 
     for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(
+            vec![Statement::Expression(expect.into())],
+            statements(program)
+        );
+    }
+
+    let tests = vec![
+        (
+            "xs[1:2:3:4]",
+            "Expected `]` to match `[` at line 1, column 3, got `:` at line 1, column 9",
+        ),
+        (
+            "xs[]",
+            "Expected an expression, got `]` at line 1, column 4",
+        ),
+    ];
+    for (input, expected) in tests {
         let mut parser = Parser::new(Lexer::new(input.to_string()));
-        let program = parser.parse_program();
+        parser.parse_program();
+        assert_eq!(parser.errors[0].to_string(), expected, "{}", input);
+    }
+}
+
+#[test]
+pub fn test_range_expression() {
+    let ident = |name: &str| Box::new(Expression::Ident(Ident(String::from(name))).into());
+    let int = |i: i64| Box::new(Expression::Literal(Literal::Int(i)).into());
+    let tests = vec![
+        (
+            "0..n",
+            Expression::Range {
+                start: int(0),
+                end: ident("n"),
+                inclusive: false,
+                step: None,
+            },
+        ),
+        (
+            "1..=n + 1 step 2",
+            Expression::Range {
+                start: int(1),
+                end: Box::new(Expression::Infix(Infix::Plus, ident("n"), int(1)).into()),
+                inclusive: true,
+                step: Some(int(2)),
+            },
+        ),
+        (
+            "a < 0..3",
+            Expression::Infix(
+                Infix::LessThan,
+                ident("a"),
+                Box::new(
+                    Expression::Range {
+                        start: int(0),
+                        end: int(3),
+                        inclusive: false,
+                        step: None,
+                    }
+                    .into(),
+                ),
+            ),
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(
+            vec![Statement::Expression(expect.into())],
+            statements(program)
+        );
+    }
+}
+
+#[test]
+pub fn test_call_expression() {
+    let tests: Vec<(&str, Statement)> = vec![
+        (
+            "add(1, 3+4)",
+            Statement::Expression(
+                Expression::Call {
+                    function: Box::new(Expression::Ident(Ident(String::from("add"))).into()),
+                    args: vec![
+                        Expression::Literal(Literal::Int(1)).into(),
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Literal(Literal::Int(3)).into()),
+                            Box::new(Expression::Literal(Literal::Int(4)).into()),
+                        )
+                        .into(),
+                    ],
+                    kwargs: vec![],
+                    optional: false,
+                }
+                .into(),
+            ),
+        ),
+        (
+            "f(x, sep: \", \")",
+            Statement::Expression(
+                Expression::Call {
+                    function: Box::new(Expression::Ident(Ident(String::from("f"))).into()),
+                    args: vec![Expression::Ident(Ident(String::from("x"))).into()],
+                    kwargs: vec![(
+                        Ident(String::from("sep")),
+                        Expression::Literal(Literal::String(String::from(", "))).into(),
+                    )],
+                    optional: false,
+                }
+                .into(),
+            ),
+        ),
+        (
+            "f(a, b,)",
+            Statement::Expression(
+                Expression::Call {
+                    function: Box::new(Expression::Ident(Ident(String::from("f"))).into()),
+                    args: vec![
+                        Expression::Ident(Ident(String::from("a"))).into(),
+                        Expression::Ident(Ident(String::from("b"))).into(),
+                    ],
+                    kwargs: vec![],
+                    optional: false,
+                }
+                .into(),
+            ),
+        ),
+        (
+            "f(x, k: 1,)",
+            Statement::Expression(
+                Expression::Call {
+                    function: Box::new(Expression::Ident(Ident(String::from("f"))).into()),
+                    args: vec![Expression::Ident(Ident(String::from("x"))).into()],
+                    kwargs: vec![(
+                        Ident(String::from("k")),
+                        Expression::Literal(Literal::Int(1)).into(),
+                    )],
+                    optional: false,
+                }
+                .into(),
+            ),
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
+    }
+}
+
+#[test]
+pub fn test_array_literal() {
+    let tests: Vec<(&str, Statement)> = vec![(
+        "[1, 2 * 2, 3+3]",
+        Statement::Expression(
+            Expression::Literal(Literal::Array(vec![
+                Expression::Literal(Literal::Int(1)).into(),
+                Expression::Infix(
+                    Infix::Multiply,
+                    Box::new(Expression::Literal(Literal::Int(2)).into()),
+                    Box::new(Expression::Literal(Literal::Int(2)).into()),
+                )
+                .into(),
+                Expression::Infix(
+                    Infix::Plus,
+                    Box::new(Expression::Literal(Literal::Int(3)).into()),
+                    Box::new(Expression::Literal(Literal::Int(3)).into()),
+                )
+                .into(),
+            ]))
+            .into(),
+        ),
+    )];
 
-        check_parse_errors(parser);
-        assert_eq!(vec![expect], program.statements);
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
     }
 }
 
@@ -788,22 +1844,26 @@ pub fn test_array_literal() {
 pub fn test_index_expression() {
     let tests = vec![(
         "myArray[1+2]",
-        Statement::Expression(Expression::Index {
-            left: Box::new(Expression::Ident(Ident(String::from("myArray")))),
-            index: Box::new(Expression::Infix(
-                Infix::Plus,
-                Box::new(Expression::Literal(Literal::Int(1))),
-                Box::new(Expression::Literal(Literal::Int(2))),
-            )),
-        }),
+        Statement::Expression(
+            Expression::Index {
+                left: Box::new(Expression::Ident(Ident(String::from("myArray"))).into()),
+                index: Box::new(
+                    Expression::Infix(
+                        Infix::Plus,
+                        Box::new(Expression::Literal(Literal::Int(1)).into()),
+                        Box::new(Expression::Literal(Literal::Int(2)).into()),
+                    )
+                    .into(),
+                ),
+                optional: false,
+            }
+            .into(),
+        ),
     )];
 
     for (input, expect) in tests {
-        let mut parser = Parser::new(Lexer::new(input.to_string()));
-        let program = parser.parse_program();
-
-        check_parse_errors(parser);
-        assert_eq!(vec![expect], program.statements);
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
     }
 }
 
@@ -812,61 +1872,560 @@ pub fn test_hash_literal() {
     let tests = vec![
         (
             "{\"one\": 1, \"two\": 2, \"three\": 3}",
-            Statement::Expression(Expression::Literal(Literal::Hash(vec![
-                (
-                    Expression::Literal(Literal::String(String::from("one"))),
-                    Expression::Literal(Literal::Int(1)),
-                ),
-                (
-                    Expression::Literal(Literal::String(String::from("two"))),
-                    Expression::Literal(Literal::Int(2)),
-                ),
-                (
-                    Expression::Literal(Literal::String(String::from("three"))),
-                    Expression::Literal(Literal::Int(3)),
-                ),
-            ]))),
+            Statement::Expression(
+                Expression::Literal(Literal::Hash(vec![
+                    (
+                        Expression::Literal(Literal::String(String::from("one"))).into(),
+                        Expression::Literal(Literal::Int(1)).into(),
+                    ),
+                    (
+                        Expression::Literal(Literal::String(String::from("two"))).into(),
+                        Expression::Literal(Literal::Int(2)).into(),
+                    ),
+                    (
+                        Expression::Literal(Literal::String(String::from("three"))).into(),
+                        Expression::Literal(Literal::Int(3)).into(),
+                    ),
+                ]))
+                .into(),
+            ),
         ),
         (
             "{}",
-            Statement::Expression(Expression::Literal(Literal::Hash(vec![]))),
+            Statement::Expression(Expression::Literal(Literal::Hash(vec![])).into()),
         ),
         (
             "{\"one\": 0 + 1, \"two\": 10 - 8, \"three\": 15 / 5}",
-            Statement::Expression(Expression::Literal(Literal::Hash(vec![
-                (
-                    Expression::Literal(Literal::String(String::from("one"))),
-                    Expression::Infix(
-                        Infix::Plus,
-                        Box::new(Expression::Literal(Literal::Int(0))),
-                        Box::new(Expression::Literal(Literal::Int(1))),
+            Statement::Expression(
+                Expression::Literal(Literal::Hash(vec![
+                    (
+                        Expression::Literal(Literal::String(String::from("one"))).into(),
+                        Expression::Infix(
+                            Infix::Plus,
+                            Box::new(Expression::Literal(Literal::Int(0)).into()),
+                            Box::new(Expression::Literal(Literal::Int(1)).into()),
+                        )
+                        .into(),
                     ),
-                ),
-                (
-                    Expression::Literal(Literal::String(String::from("two"))),
-                    Expression::Infix(
-                        Infix::Minus,
-                        Box::new(Expression::Literal(Literal::Int(10))),
-                        Box::new(Expression::Literal(Literal::Int(8))),
+                    (
+                        Expression::Literal(Literal::String(String::from("two"))).into(),
+                        Expression::Infix(
+                            Infix::Minus,
+                            Box::new(Expression::Literal(Literal::Int(10)).into()),
+                            Box::new(Expression::Literal(Literal::Int(8)).into()),
+                        )
+                        .into(),
                     ),
-                ),
-                (
-                    Expression::Literal(Literal::String(String::from("three"))),
-                    Expression::Infix(
-                        Infix::Divide,
-                        Box::new(Expression::Literal(Literal::Int(15))),
-                        Box::new(Expression::Literal(Literal::Int(5))),
+                    (
+                        Expression::Literal(Literal::String(String::from("three"))).into(),
+                        Expression::Infix(
+                            Infix::Divide,
+                            Box::new(Expression::Literal(Literal::Int(15)).into()),
+                            Box::new(Expression::Literal(Literal::Int(5)).into()),
+                        )
+                        .into(),
                     ),
-                ),
-            ]))),
+                ]))
+                .into(),
+            ),
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let program = parse(input);
+        assert_eq!(vec![expect], statements(program));
+    }
+}
+
+#[test]
+pub fn test_node_spans() {
+    let input = "let x = 1;\nx + foo(2);";
+    let mut p = Parser::new(Lexer::new(input.to_string()));
+    let program = p.parse_program();
+    let span = |s: Span| (s.start.line, s.start.column, s.end.line, s.end.column);
+
+    assert_eq!((1, 1, 1, 11), span(program.statements[0].span));
+    assert_eq!((2, 1, 2, 12), span(program.statements[1].span));
+
+    match &program.statements[1].node {
+        Statement::Expression(Spanned {
+            node: Expression::Infix(_, left, right),
+            span: infix,
+        }) => {
+            assert_eq!((2, 1, 2, 11), span(*infix));
+            assert_eq!((2, 1, 2, 2), span(left.span));
+            assert_eq!((2, 5, 2, 11), span(right.span));
+        }
+        s => panic!("expected an infix expression statement, got {:?}", s),
+    }
+}
+
+#[test]
+pub fn test_parse_error_location() {
+    let input = "let x = 1;\n  let = 5;";
+    let mut p = Parser::new(Lexer::new(input.to_string()));
+    p.parse_program();
+
+    assert_eq!(
+        vec![ParseError::UnexpectedToken {
+            expected: Token::Ident(String::new()),
+            found: Token::Assign,
+            span: span(17, 2, 7, 1),
+        }],
+        p.errors
+    );
+    assert_eq!(
+        "Expected an identifier, got `=` at line 2, column 7",
+        p.errors[0].to_string()
+    );
+}
+
+#[test]
+pub fn test_interpolated_string() {
+    let input = r#""a ${x + 1} b""#;
+    let program = parse(input);
+
+    let expect = Statement::Expression(
+        Expression::Interpolated(vec![
+            Expression::Literal(Literal::String(String::from("a "))).into(),
+            Expression::Infix(
+                Infix::Plus,
+                Box::new(Expression::Ident(Ident(String::from("x"))).into()),
+                Box::new(Expression::Literal(Literal::Int(1)).into()),
+            )
+            .into(),
+            Expression::Literal(Literal::String(String::from(" b"))).into(),
+        ])
+        .into(),
+    );
+    assert_eq!(vec![expect], statements(program));
+}
+
+#[test]
+pub fn test_interpolated_string_errors() {
+    let tests = vec![
+        (r#""${}""#, ParseError::EmptyInterpolation(span(3, 1, 4, 0))),
+        (
+            "\"${x y}\"",
+            ParseError::UnexpectedToken {
+                expected: Token::Eof,
+                found: Token::Ident(String::from("y")),
+                span: span(5, 1, 6, 1),
+            },
+        ),
+    ];
+
+    for (input, expect) in tests {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        parser.parse_program();
+        assert_eq!(vec![expect], parser.errors);
+    }
+}
+
+#[test]
+pub fn test_illegal_token_error() {
+    let mut parser = Parser::new(Lexer::new("let x = 0b102;".to_string()));
+    parser.parse_program();
+
+    assert_eq!(
+        vec![ParseError::Lex(LexError::InvalidNumber(
+            String::from("0b102"),
+            span(8, 1, 9, 5)
+        ))],
+        parser.errors
+    );
+    assert_eq!(
+        "Invalid number literal `0b102` at line 1, column 9",
+        parser.errors[0].to_string()
+    );
+}
+
+#[test]
+pub fn test_error_recovery() {
+    let input =
+        "let x = ;\nlet y = 2;\nlet f = fn(a, 1) { a };\nlet g = fn() { let = 1; y };\nprint(y);";
+    let mut parser = Parser::new(Lexer::new(input.to_string()));
+    let program = parser.parse_program();
+
+    assert_eq!(
+        vec![
+            ParseError::ExpectedExpression {
+                found: Token::SemiColon,
+                span: span(8, 1, 9, 1),
+            },
+            ParseError::InvalidParameter {
+                found: Token::Int(1),
+                span: span(35, 3, 15, 1),
+            },
+            ParseError::UnexpectedToken {
+                expected: Token::Ident(String::new()),
+                found: Token::Assign,
+                span: span(64, 4, 20, 1),
+            },
+        ],
+        parser.errors
+    );
+    // the statements around the errors are still parsed
+    assert_eq!(3, program.statements.len());
+    match &program.statements[1].node {
+        Statement::Let(
+            _,
+            Spanned {
+                node: Expression::Fn { body, .. },
+                ..
+            },
+        ) => assert_eq!(1, body.len()),
+        s => panic!("expected a function, got {:?}", s),
+    }
+}
+
+#[test]
+pub fn test_malformed_input() {
+    let tests =
+        vec![
+        ("+ * 3", "Expected an expression, got `*` at line 1, column 3"),
+        ("let x = ;", "Expected an expression, got `;` at line 1, column 9"),
+        (
+            "let x = 5 +",
+            "Expected an expression, got end of input at line 1, column 12",
+        ),
+        (
+            "update x = 1 *",
+            "Expected an expression, got end of input at line 1, column 15",
+        ),
+        (
+            "return",
+            "Expected an expression, got end of input at line 1, column 7",
+        ),
+        ("import 5;", "Expected an identifier, got `5` at line 1, column 8"),
+        (")))", "Expected an expression, got `)` at line 1, column 1"),
+        (
+            "(((",
+            "Expected an expression, got end of input at line 1, column 4",
+        ),
+        (
+            "[1, 2",
+            "Expected `]` to match `[` at line 1, column 1, got end of input at line 1, column 6",
+        ),
+        (
+            "{1: 2",
+            "Expected `}` to match `{` at line 1, column 1, got end of input at line 1, column 6",
+        ),
+        (
+            "f(1, 2",
+            "Expected `)` to match `(` at line 1, column 2, got end of input at line 1, column 7",
+        ),
+        (
+            "(1 + 2;",
+            "Expected `)` to match `(` at line 1, column 1, got `;` at line 1, column 7",
+        ),
+        (
+            "{1: ",
+            "Expected an expression, got end of input at line 1, column 5",
+        ),
+        (
+            "f(1,",
+            "Expected an expression, got end of input at line 1, column 5",
+        ),
+        ("fn(x) x", "Expected `{`, got `x` at line 1, column 7"),
+        (
+            "fn(a, 1) {}",
+            "Expected function parameter to be an identifier, got `1` at line 1, column 7",
+        ),
+        ("fn(x) { x", "Unterminated block starting at line 1, column 7"),
+        (
+            "if (x) { 1 } else {",
+            "Unterminated block starting at line 1, column 19",
+        ),
+        (
+            "while (true) { 1",
+            "Unterminated block starting at line 1, column 14",
+        ),
+        (
+            "x[1",
+            "Expected `]` to match `[` at line 1, column 2, got end of input at line 1, column 4",
+        ),
+        (
+            "let s = \"unterminated",
+            "Unterminated string starting at line 1, column 9",
         ),
     ];
 
     for (input, expect) in tests {
         let mut parser = Parser::new(Lexer::new(input.to_string()));
-        let program = parser.parse_program();
+        parser.parse_program();
+        let errors: Vec<String> = parser.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(vec![String::from(expect)], errors, "input: {}", input);
+    }
+}
+
+fn parse_valid(input: &str) -> Program {
+    let mut parser = Parser::new(Lexer::new(input.to_string()));
+    let program = parser.parse_program();
+    check_parse_errors(parser);
+    program
+}
+
+/// Parses `input`, failing on any error, and round-trips it through both
+/// `Display` and the formatter, so every input a test parses also checks the
+/// printer.
+fn parse(input: &str) -> Program {
+    let program = parse_valid(input);
+    assert_round_trip(&program);
+    assert_format_round_trip(input, &program);
+    program
+}
+
+/// Prints `program` and checks the output parses back to the same tree and
+/// prints the same way a second time.
+fn assert_round_trip(program: &Program) {
+    let printed = program.to_string();
+    let reparsed = parse_valid(&printed);
+    assert_eq!(*program, reparsed, "printed as:\n{}", printed);
+    assert_eq!(printed, reparsed.to_string());
+}
+
+/// Formats `input`, comments and all, and checks the output parses back to
+/// `program` and formats to itself.
+fn assert_format_round_trip(input: &str, program: &Program) {
+    let formatted = format(input).unwrap_or_else(|e| panic!("{:?}\ninput: {}", e, input));
+    assert_eq!(
+        *program,
+        parse_valid(&formatted),
+        "formatted as:\n{}",
+        formatted
+    );
+    assert_eq!(Ok(formatted.clone()), format(&formatted));
+}
+
+#[test]
+fn test_print_round_trip() {
+    let tests = vec![
+        "let x = 5; let y = true; let foobar = y;",
+        "update x = 5; update x += 1; update x %= 2; update grid[y][x] *= 3;",
+        "return 5; return 10; return add(15);",
+        "foobar; \"hello world\"; 5; 1.5; 2.5e10; 1e-7; 0.1; true; false;",
+        "99999999999999999999999; -9223372036854775808;",
+        "-a * b; !-a; +a; ~a; - -a; !!true; -(a + b); -a ** 2; (-a) ** 2; 2 ** -1;",
+        "a+b+c; a+b-c; a*b*c; a*b/c; a/b+c; a+b/c; a + b * c + d / e - f;",
+        "a - (b - c); a / (b * c); a + (b + c); (a + b) * c; a % (b % c);",
+        "5 > 4 == 3 < 4; 5 < 4 != 3 > 4; 5 >= 4 == 3 <= 4; (a < b) < c; a < (b < c);",
+        "3 + 4 * 5 == 3 * 1 + 4 * 5; 3 > 5 == false; 3 < 5 == true;",
+        "a * [1, 2, 3, 4][b * c] * d; add(a, b)[0]; (a + b)(c); f(1)(2); a[0][1];",
+        "a || b && c == d; a && b || c; a < 1 && !b; (a || b) && c;",
+        "a | b ^ c & d; a & 1 == 0; 1 << a + 2; (1 << a) + 2; ~a >> 1;",
+        "a ** b ** 2; (a ** b) ** 2; -a ** 2 * b; a ** b(1); a ** b[0];",
+        "if ( x > y ) { x }; if ((5 * 5) + 5 >= 30) { true };",
+        "if ( x > y ) { x } else { y }",
+        "if ( x > y ) { x } else if (y == x) { 1 } else { y }",
+        "if (x) { if (y) { 1 } else { 2 } } else { if (z) { 3 }; 4 }",
+        "while (x < 10) { break; } for (item in items) { continue; }",
+        "while (true) { let f = fn() { 1 }; update i -= 1; if (i) { break; } }",
+        "fn() { return true; }; fn(a,b) { return a+b; }; let sub = fn (a,b) { a-b };",
+        "fn(x) { x }(5); fn() {}; if (x) {} else {};",
+        "add(1, 3+4); [1, 2 * 2, 3+3]; []; myArray[1+2]; {}; {\"one\": 1, 2: [3], true: {}};",
+        "import std; let x = 1;\nx + foo(2);",
+        "match (x) { 0 => a, -1 => b, -9223372036854775808 => c, \"s\" => d, true => e, 1.5 => f }",
+        "match (x) { [] => 1, [a, ...] => 2, [...rest] => 3, [a, [b, ...c]] => 4, } + 1",
+        "match (x) { {\"a\": 1, 2: [b]} if b > 1 => { let y = b; y } _ => {} }; match (y) {}",
+        "let [a, b, ...rest] = xs; let {\"name\": n, age, \"x y\": [_, z]} = person; let _ = 1;",
+        "let f = fn(a, b = 2, ...rest) { a }; f(1, b: 3); fn(...xs) {}; g(x: 1, y: [2]);",
+        "fn even(n) { if (n == 0) { true } else { odd(n - 1) } } fn odd(n) { !even(n) }",
+        "fn f(a, ...rest) {} (f)(1);",
+        "for (i in 0..len(xs) - 1 step 2) {} (1..=3)[0]; 1..(2..3); (1..2)..3; -(0..1);",
+        "xs |> map(f) |> len; f(a |> g); a |> (b |> c); (a |> b) + 1;",
+        "xs[1:]; xs[:-1]; xs[::2]; xs[a + 1:b:-1]; s[:]; h?[1:2]; xs[0][1:][0];",
+        "let x = h?[\"a\"]?[0] ?? null; f?.(1)?.(2); (a ?? b) ?? c; a ?? (b ?? c); match (x) { null => 1 }",
+        r#""a ${x + 1} b"; "${x}"; "${"x"}"; "${x}${y}"; "a ${"b"}c ${d}";"#,
+        r#""quote \" slash \\ tab \t newline \n dollar \${x} $ end"; "\u{1}\0";"#,
+        r#""outer ${"inner ${x}" + "\"" } done"; "${fn(x) { x }(1)}";"#,
+        "# leading\nlet x = 1; # trailing\n\n/* block */ f(a, # first\n  b);\n# last",
+        "let h = {\n  # key\n  \"a\": 1, # one\n  \"b\": [2, /* two */ 3],\n};",
+        "match (x) {\n  # zero\n  0 => 1, # one\n  _ => {\n    # body\n    2\n  }\n}",
+        r#"match (x) { _ => { {"a": 1}["a"] } 1 => { {"a": 1} == y } 2 => { {"a": 1}..=x } }"#,
+        "if (x) {\n  # then\n  1\n} else {\n  2 # else\n}\nxs[1:2] |> g(k: 1..=3); /* slice */",
+        include_str!("../../libraries/std.hera"),
+    ];
+
+    for input in tests {
+        parse(input);
+    }
+}
+
+#[test]
+fn test_print_round_trip_examples() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/examples");
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "hera") {
+            parse(&std::fs::read_to_string(&path).unwrap());
+        }
+    }
+}
+
+/// A small xorshift generator, so the random trees are the same on every run.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % n
+    }
+}
 
-        check_parse_errors(parser);
-        assert_eq!(vec![expect], program.statements);
+fn random_expression(rng: &mut Rng, depth: u32) -> Spanned<Expression> {
+    const INFIXES: [Infix; 20] = [
+        Infix::Plus,
+        Infix::Minus,
+        Infix::Divide,
+        Infix::Multiply,
+        Infix::Equal,
+        Infix::Modulus,
+        Infix::NotEqual,
+        Infix::GreaterThanEqual,
+        Infix::GreaterThan,
+        Infix::LessThanEqual,
+        Infix::LessThan,
+        Infix::And,
+        Infix::Or,
+        Infix::BitAnd,
+        Infix::BitOr,
+        Infix::BitXor,
+        Infix::ShiftLeft,
+        Infix::ShiftRight,
+        Infix::Power,
+        Infix::Pipe,
+    ];
+    const PREFIXES: [Prefix; 4] = [Prefix::Plus, Prefix::Minus, Prefix::Not, Prefix::BitNot];
+
+    let next = |rng: &mut Rng| Box::new(random_expression(rng, depth - 1));
+    let choice = if depth == 0 {
+        rng.below(3)
+    } else {
+        rng.below(12)
+    };
+    let expr = match choice {
+        0 => Expression::Ident(Ident(String::from(["a", "b", "c"][rng.below(3) as usize]))),
+        1 => Expression::Literal(Literal::Int(rng.below(100) as i64)),
+        2 => Expression::Literal(Literal::String(String::from("s"))),
+        3..=5 => Expression::Infix(
+            INFIXES[rng.below(INFIXES.len() as u64) as usize].clone(),
+            next(rng),
+            next(rng),
+        ),
+        6 => Expression::Prefix(
+            PREFIXES[rng.below(PREFIXES.len() as u64) as usize].clone(),
+            next(rng),
+        ),
+        7 => Expression::Call {
+            function: next(rng),
+            args: (0..rng.below(3)).map(|_| *next(rng)).collect(),
+            kwargs: (0..rng.below(2))
+                .map(|_| (Ident(String::from("k")), *next(rng)))
+                .collect(),
+            optional: rng.below(2) == 0,
+        },
+        8 => Expression::Range {
+            start: next(rng),
+            end: next(rng),
+            inclusive: rng.below(2) == 0,
+            step: if rng.below(2) == 0 {
+                Some(next(rng))
+            } else {
+                None
+            },
+        },
+        9 => Expression::Slice {
+            left: next(rng),
+            start: (rng.below(2) == 0).then(|| next(rng)),
+            end: (rng.below(2) == 0).then(|| next(rng)),
+            step: (rng.below(2) == 0).then(|| next(rng)),
+            optional: rng.below(2) == 0,
+        },
+        10 => Expression::Match {
+            subject: next(rng),
+            arms: (0..rng.below(3))
+                .map(|_| {
+                    MatchArm {
+                        pattern: random_pattern(rng, 2),
+                        guard: (rng.below(2) == 0).then(|| *next(rng)),
+                        body: (0..rng.below(2))
+                            .map(|_| {
+                                let mut expr = *next(rng);
+                                if rng.below(2) == 0 {
+                                    hash_first(&mut expr.node);
+                                }
+                                Statement::Expression(expr).into()
+                            })
+                            .collect(),
+                    }
+                    .into()
+                })
+                .collect(),
+        },
+        _ => Expression::Index {
+            left: next(rng),
+            index: next(rng),
+            optional: rng.below(2) == 0,
+        },
+    };
+    expr.into()
+}
+
+/// Wraps the leftmost operand of `expr` in a hash literal, so that `expr` is
+/// written starting with `{`.
+fn hash_first(expr: &mut Expression) {
+    match expr {
+        Expression::Infix(_, left, _)
+        | Expression::Range { start: left, .. }
+        | Expression::Call { function: left, .. }
+        | Expression::Index { left, .. }
+        | Expression::Slice { left, .. } => hash_first(&mut left.node),
+        _ => {
+            let value = std::mem::replace(expr, Expression::Literal(Literal::Hash(vec![])));
+            let key = Expression::Literal(Literal::String(String::from("k")));
+            *expr = Expression::Literal(Literal::Hash(vec![(key.into(), value.into())]));
+        }
+    }
+}
+
+fn random_pattern(rng: &mut Rng, depth: u32) -> Pattern {
+    let choice = if depth == 0 {
+        rng.below(3)
+    } else {
+        rng.below(5)
+    };
+    match choice {
+        0 => Pattern::Wildcard,
+        1 => Pattern::Ident(Ident(String::from("p"))),
+        2 => Pattern::Literal(Literal::Int(rng.below(100) as i64 - 50).into()),
+        3 => Pattern::Array {
+            items: (0..rng.below(3))
+                .map(|_| random_pattern(rng, depth - 1))
+                .collect(),
+            rest: (rng.below(2) == 0).then(|| Box::new(random_pattern(rng, 0))),
+        },
+        _ => Pattern::Hash(
+            (0..rng.below(3))
+                .map(|i| {
+                    let key = Literal::String(format!("k{}", i)).into();
+                    (key, random_pattern(rng, depth - 1))
+                })
+                .collect(),
+        ),
+    }
+}
+
+#[test]
+fn test_print_random_expressions() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..2000 {
+        let program = Program {
+            statements: vec![Statement::Expression(random_expression(&mut rng, 5)).into()],
+        };
+        assert_round_trip(&program);
+        assert_format_round_trip(&program.to_string(), &program);
     }
 }
@@ -0,0 +1,35 @@
+use crate::{parser::error::ParseError, token::Span};
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    Parse(ParseError),
+    /// A comment the printer can't keep between the same tokens, such as one
+    /// inside `${...}` or a parameter list.
+    MovedComment(Span),
+    /// The formatted code doesn't parse back to the same program, which is a
+    /// bug in the printer.
+    ChangedProgram,
+}
+
+impl From<ParseError> for FormatError {
+    fn from(e: ParseError) -> Self {
+        FormatError::Parse(e)
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Parse(e) => write!(f, "{}", e),
+            FormatError::MovedComment(span) => {
+                write!(f, "Cannot format the comment at {} in place", span)
+            }
+            FormatError::ChangedProgram => {
+                write!(f, "Formatting would change the meaning of the code")
+            }
+        }
+    }
+}
+
+impl Error for FormatError {}
@@ -0,0 +1,927 @@
+pub mod error;
+
+#[cfg(test)]
+pub mod test;
+
+use crate::{
+    ast::*,
+    lexer::Lexer,
+    parser::Parser,
+    token::{Comment, Position, Span, SpannedToken, StringPart, Token},
+};
+use error::FormatError;
+use std::fmt::{self, Formatter};
+
+/// Array and hash literals that would run past this column are written with
+/// one item per line.
+const MAX_WIDTH: usize = 100;
+
+/// Rewrites `source` in the canonical style, keeping its comments. Rather
+/// than move a comment to somewhere it can be written, like out of a
+/// parameter list, the file is left for the user to rearrange.
+pub fn format(source: &str) -> Result<String, Vec<FormatError>> {
+    let mut parser = Parser::new(Lexer::new(source.to_string()));
+    let program = parser.parse_program();
+    if !parser.errors.is_empty() {
+        return Err(parser.errors.into_iter().map(FormatError::from).collect());
+    }
+
+    let mut printer = Printer::with_comments(source, &parser.l.comments);
+    printer.program(&program);
+    let formatted = reparse(&program, printer.out)?;
+    match moved_comment(source, &formatted) {
+        Some(span) => Err(vec![FormatError::MovedComment(span)]),
+        None => Ok(formatted),
+    }
+}
+
+/// Checks that `formatted` parses back to `program`, so that a printer bug
+/// fails the format instead of rewriting the file into different code.
+fn reparse(program: &Program, formatted: String) -> Result<String, Vec<FormatError>> {
+    let mut parser = Parser::new(Lexer::new(formatted.clone()));
+    if parser.parse_program() == *program && parser.errors.is_empty() {
+        Ok(formatted)
+    } else {
+        Err(vec![FormatError::ChangedProgram])
+    }
+}
+
+/// Writes an AST back out as Hera source that parses to the same tree.
+/// Operands are parenthesized only where the parser would otherwise group
+/// them differently.
+///
+/// When given the source a program was parsed from, the printer also puts
+/// its comments and blank lines back between the statements they came with.
+#[derive(Default)]
+pub struct Printer<'a> {
+    pub out: String,
+    indent: usize,
+    /// Set while measuring whether a literal or pipeline fits on a single
+    /// line.
+    flat: bool,
+    source: &'a str,
+    /// The comments that have not been written yet.
+    comments: &'a [Comment],
+    /// The source line the last statement or comment ended on, or `None` at
+    /// the start of a block.
+    last_line: Option<usize>,
+}
+
+impl<'a> Printer<'a> {
+    pub fn with_comments(source: &'a str, comments: &'a [Comment]) -> Self {
+        Printer {
+            source,
+            comments,
+            ..Printer::default()
+        }
+    }
+
+    pub fn program(&mut self, program: &Program) {
+        for statement in &program.statements {
+            self.statement_line(statement, false);
+        }
+        self.comments_before(usize::MAX);
+    }
+
+    /// Writes a statement on its own line, along with the comments before it
+    /// and any comment following it on the same line. The trailing expression
+    /// of a block is left without its semicolon since it is the block's value.
+    fn statement_line(&mut self, statement: &Spanned<Statement>, is_last: bool) {
+        self.comments_before(statement.span.start.offset);
+        self.blank_line_before(statement.span.start.line);
+        self.indentation();
+        match &statement.node {
+            Statement::Expression(expr) if is_last => self.spanned_expression(expr),
+            node => self.statement(node),
+        }
+
+        self.last_line = Some(statement.span.end.line);
+        self.trailing_comments(statement.span.end);
+        self.out.push('\n');
+    }
+
+    /// Writes the comments that follow `end` on the same line, with nothing
+    /// but separators in between.
+    fn trailing_comments(&mut self, end: Position) {
+        let comments = self.comments;
+        for comment in comments {
+            let gap = self.source.get(end.offset..comment.span.start.offset);
+            let separated = gap.is_some_and(|gap| {
+                gap.chars()
+                    .all(|c| c == ' ' || c == '\t' || c == ',' || c == ';')
+            });
+            if comment.span.start.line != end.line || !separated {
+                break;
+            }
+            self.out.push(' ');
+            self.out.push_str(&comment.text);
+            self.last_line = Some(comment.span.end.line);
+            self.comments = &self.comments[1..];
+        }
+    }
+
+    fn comments_before(&mut self, offset: usize) {
+        let comments = self.comments;
+        for comment in comments {
+            if comment.span.start.offset >= offset {
+                break;
+            }
+            self.blank_line_before(comment.span.start.line);
+            self.indentation();
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+            self.last_line = Some(comment.span.end.line);
+            self.comments = &self.comments[1..];
+        }
+    }
+
+    /// Keeps a single blank line where the source had one or more.
+    fn blank_line_before(&mut self, line: usize) {
+        if let Some(last) = self.last_line {
+            if line > last + 1 {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn indentation(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+    }
+
+    fn column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map_or(0, |i| i + 1);
+        self.out[line_start..].chars().count()
+    }
+
+    fn block(&mut self, block: &[Spanned<Statement>]) {
+        let last = match block.last() {
+            Some(last) => last,
+            None => {
+                self.out.push_str("{}");
+                return;
+            }
+        };
+
+        self.out.push_str("{\n");
+        self.indent += 1;
+        self.last_line = None;
+        for (i, statement) in block.iter().enumerate() {
+            self.statement_line(statement, i == block.len() - 1);
+        }
+        let end = self.closing_brace(last.span.end.offset);
+        self.comments_before(end);
+        self.indent -= 1;
+        self.indentation();
+        self.out.push('}');
+    }
+
+    /// Finds the `}` closing a block whose last statement ends at `offset`,
+    /// so that comments after that statement stay inside the block.
+    fn closing_brace(&self, mut offset: usize) -> usize {
+        let source = self.source.as_bytes();
+        let mut comments = self.comments.iter().peekable();
+        while offset < source.len() {
+            match comments.peek() {
+                Some(comment) if comment.span.start.offset <= offset => {
+                    offset = offset.max(comment.span.end.offset);
+                    comments.next();
+                    continue;
+                }
+                _ => {}
+            }
+            if source[offset] == b'}' {
+                break;
+            }
+            offset += 1;
+        }
+        offset
+    }
+
+    pub fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let(pattern, value) => {
+                self.out.push_str("let ");
+                self.pattern(pattern);
+                self.out.push_str(" = ");
+                self.spanned_expression(value);
+                self.out.push(';');
+            }
+            Statement::Update {
+                target,
+                operator,
+                value,
+            } => {
+                self.out.push_str("update ");
+                self.spanned_expression(target);
+                self.out.push(' ');
+                if let Some(operator) = operator {
+                    self.out.push_str(&operator.to_string());
+                }
+                self.out.push_str("= ");
+                self.spanned_expression(value);
+                self.out.push(';');
+            }
+            Statement::Fn {
+                name,
+                params,
+                rest,
+                body,
+            } => {
+                self.out.push_str("fn ");
+                self.out.push_str(&name.0);
+                self.function(params, rest, body);
+            }
+            Statement::Return(value) => {
+                self.out.push_str("return ");
+                self.spanned_expression(value);
+                self.out.push(';');
+            }
+            Statement::Expression(expr) => {
+                self.spanned_expression(expr);
+                self.out.push(';');
+            }
+            Statement::Import(ident) => {
+                self.out.push_str("import ");
+                self.out.push_str(&ident.0);
+                self.out.push(';');
+            }
+            Statement::While { condition, body } => {
+                self.out.push_str("while (");
+                self.spanned_expression(condition);
+                self.out.push_str(") ");
+                self.block(body);
+            }
+            Statement::For {
+                ident,
+                iterable,
+                body,
+            } => {
+                self.out.push_str("for (");
+                self.out.push_str(&ident.0);
+                self.out.push_str(" in ");
+                self.spanned_expression(iterable);
+                self.out.push_str(") ");
+                self.block(body);
+            }
+            Statement::Break => self.out.push_str("break;"),
+            Statement::Continue => self.out.push_str("continue;"),
+        }
+    }
+
+    pub fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Ident(ident) => self.out.push_str(&ident.0),
+            Expression::Literal(literal) => self.literal(literal),
+            Expression::Interpolated(parts) => {
+                self.out.push('"');
+                let mut after_text = false;
+                for part in parts {
+                    match &part.node {
+                        // Text must not directly follow text, or the two
+                        // pieces would be lexed back as one.
+                        Expression::Literal(Literal::String(s))
+                            if !s.is_empty() && !after_text && parts.len() > 1 =>
+                        {
+                            escape(&mut self.out, s);
+                            after_text = true;
+                        }
+                        _ => {
+                            self.out.push_str("${");
+                            self.spanned_expression(part);
+                            self.out.push('}');
+                            after_text = false;
+                        }
+                    }
+                }
+                self.out.push('"');
+            }
+            Expression::Prefix(prefix, right) => {
+                self.out.push_str(&prefix.to_string());
+                self.operand(right, right.node.precedence() < Precedence::Prefix);
+            }
+            Expression::Infix(Infix::Pipe, left, right) if !self.flat => {
+                self.pipeline(expr, left, right)
+            }
+            Expression::Infix(infix, left, right) => {
+                let precedence = infix.precedence();
+                let (left_parens, right_parens) = if *infix == Infix::Power {
+                    (
+                        left.node.precedence() <= precedence,
+                        right.node.precedence() < Precedence::Prefix,
+                    )
+                } else {
+                    (
+                        left.node.precedence() < precedence,
+                        right.node.precedence() <= precedence,
+                    )
+                };
+                self.operand(left, left_parens);
+                self.out.push(' ');
+                self.out.push_str(&infix.to_string());
+                self.out.push(' ');
+                self.operand(right, right_parens);
+            }
+            Expression::Range {
+                start,
+                end,
+                inclusive,
+                step,
+            } => {
+                self.operand(start, start.node.precedence() < Precedence::Range);
+                self.out.push_str(if *inclusive { "..=" } else { ".." });
+                self.operand(end, end.node.precedence() <= Precedence::Range);
+                if let Some(step) = step {
+                    self.out.push_str(" step ");
+                    self.operand(step, step.node.precedence() <= Precedence::Range);
+                }
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.out.push_str("if (");
+                self.spanned_expression(condition);
+                self.out.push_str(") ");
+                self.block(consequence);
+                match alternative.as_deref() {
+                    Some(
+                        [Spanned {
+                            node: Statement::Expression(expr),
+                            ..
+                        }],
+                    ) if matches!(expr.node, Expression::If { .. }) => {
+                        self.out.push_str(" else ");
+                        self.spanned_expression(expr);
+                    }
+                    Some(alternative) => {
+                        self.out.push_str(" else ");
+                        self.block(alternative);
+                    }
+                    None => {}
+                }
+            }
+            Expression::Fn { params, rest, body } => {
+                self.out.push_str("fn");
+                self.function(params, rest, body);
+            }
+            Expression::Call {
+                function,
+                args,
+                kwargs,
+                optional,
+            } => {
+                self.operand(function, function.node.precedence() < Precedence::Call);
+                let open = if *optional { "?.(" } else { "(" };
+                let args: Vec<_> = args
+                    .iter()
+                    .map(|arg| (None, arg))
+                    .chain(kwargs.iter().map(|(name, arg)| (Some(name), arg)))
+                    .collect();
+                self.arguments(open, &args);
+            }
+            Expression::Index {
+                left,
+                index,
+                optional,
+            } => {
+                self.operand(left, left.node.precedence() < Precedence::Call);
+                self.out.push_str(if *optional { "?[" } else { "[" });
+                self.spanned_expression(index);
+                self.out.push(']');
+            }
+            Expression::Slice {
+                left,
+                start,
+                end,
+                step,
+                optional,
+            } => {
+                self.operand(left, left.node.precedence() < Precedence::Call);
+                self.out.push_str(if *optional { "?[" } else { "[" });
+                if let Some(start) = start {
+                    self.spanned_expression(start);
+                }
+                self.out.push(':');
+                if let Some(end) = end {
+                    self.spanned_expression(end);
+                }
+                if let Some(step) = step {
+                    self.out.push(':');
+                    self.spanned_expression(step);
+                }
+                self.out.push(']');
+            }
+            Expression::Match { subject, arms } => {
+                self.out.push_str("match (");
+                self.spanned_expression(subject);
+                self.out.push_str(") ");
+                if arms.is_empty() {
+                    self.out.push_str("{}");
+                    return;
+                }
+
+                self.out.push_str("{\n");
+                self.indent += 1;
+                self.last_line = None;
+                for arm in arms {
+                    self.comments_before(arm.span.start.offset);
+                    self.blank_line_before(arm.span.start.line);
+                    self.indentation();
+                    self.match_arm(arm);
+                    self.last_line = Some(arm.span.end.line);
+                    self.trailing_comments(arm.span.end);
+                    self.out.push('\n');
+                }
+                self.indent -= 1;
+                self.indentation();
+                self.out.push('}');
+            }
+        }
+    }
+
+    /// Writes a chain of `|>` on one line if it fits, or otherwise with each
+    /// stage on its own line below the value being piped.
+    fn pipeline(
+        &mut self,
+        expr: &Expression,
+        left: &Spanned<Expression>,
+        right: &Spanned<Expression>,
+    ) {
+        let mut flat = Printer {
+            flat: true,
+            source: self.source,
+            ..Printer::default()
+        };
+        flat.expression(expr);
+        if !flat.out.contains('\n') && self.column() + flat.out.chars().count() <= MAX_WIDTH {
+            self.out.push_str(&flat.out);
+            return;
+        }
+
+        let mut stages = vec![right];
+        let mut head = left;
+        while let Expression::Infix(Infix::Pipe, left, right) = &head.node {
+            stages.push(right);
+            head = left;
+        }
+        self.operand(head, head.node.precedence() < Precedence::Pipe);
+        self.indent += 1;
+        for stage in stages.into_iter().rev() {
+            self.out.push('\n');
+            self.indentation();
+            self.out.push_str("|> ");
+            self.operand(stage, stage.node.precedence() <= Precedence::Pipe);
+        }
+        self.indent -= 1;
+    }
+
+    /// Writes the parameter list and body that follow `fn` or `fn name`.
+    fn function(&mut self, params: &[Param], rest: &Option<Ident>, body: &BlockStatement) {
+        self.out.push('(');
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.out.push_str(&param.name.0);
+            if let Some(default) = &param.default {
+                self.out.push_str(" = ");
+                self.spanned_expression(default);
+            }
+        }
+        if let Some(rest) = rest {
+            if !params.is_empty() {
+                self.out.push_str(", ");
+            }
+            self.out.push_str("...");
+            self.out.push_str(&rest.0);
+        }
+        self.out.push_str(") ");
+        self.block(body);
+    }
+
+    /// Writes an arm whose body is a single expression on one line, unless
+    /// that expression starts with a hash literal, which would be read as a
+    /// block, or the block has comments of its own around the expression.
+    fn match_arm(&mut self, arm: &Spanned<MatchArm>) {
+        let (span, arm) = (arm.span, &arm.node);
+        self.pattern(&arm.pattern);
+        if let Some(guard) = &arm.guard {
+            self.out.push_str(" if ");
+            self.spanned_expression(guard);
+        }
+        self.out.push_str(" => ");
+        match arm.body.as_slice() {
+            [Spanned {
+                node: Statement::Expression(expr),
+                ..
+            }] if !starts_with_hash(&expr.node)
+                && !self.comments_around(expr.span, span.end.offset) =>
+            {
+                self.spanned_expression(expr);
+                self.out.push(',');
+            }
+            body => self.block(body),
+        }
+    }
+
+    pub fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard => self.out.push('_'),
+            Pattern::Ident(ident) => self.out.push_str(&ident.0),
+            Pattern::Literal(literal) => self.spanned_literal(&literal.node, literal.span),
+            Pattern::Array { items, rest } => {
+                self.out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(item);
+                }
+                if let Some(rest) = rest {
+                    if !items.is_empty() {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str("...");
+                    if **rest != Pattern::Wildcard {
+                        self.pattern(rest);
+                    }
+                }
+                self.out.push(']');
+            }
+            Pattern::Hash(pairs) => {
+                self.out.push('{');
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    match (&key.node, value) {
+                        (Literal::String(key), Pattern::Ident(Ident(name))) if key == name => {
+                            self.out.push_str(name)
+                        }
+                        _ => {
+                            self.spanned_literal(&key.node, key.span);
+                            self.out.push_str(": ");
+                            self.pattern(value);
+                        }
+                    }
+                }
+                self.out.push('}');
+            }
+        }
+    }
+
+    fn operand(&mut self, expr: &Spanned<Expression>, parens: bool) {
+        if parens {
+            self.out.push('(');
+            self.spanned_expression(expr);
+            self.out.push(')');
+        } else {
+            self.spanned_expression(expr);
+        }
+    }
+
+    /// Writes `expr`, keeping the spelling its number literals have in the
+    /// source.
+    fn spanned_expression(&mut self, expr: &Spanned<Expression>) {
+        match &expr.node {
+            Expression::Literal(literal) => self.spanned_literal(literal, expr.span),
+            node => self.expression(node),
+        }
+    }
+
+    /// Writes `literal`, found at `span` in the source. Numbers are copied
+    /// from the source so that `0xFF` or `1_000` don't come out in decimal.
+    fn spanned_literal(&mut self, literal: &Literal, span: Span) {
+        let text = match literal {
+            Literal::Int(_) | Literal::BigInt(_) | Literal::Float(_) => self.number_text(span),
+            _ => None,
+        };
+        match text {
+            Some(text) => self.out.push_str(&text),
+            None => self.literal(literal),
+        }
+    }
+
+    /// The source spelling of the number at `span`, which may also cover
+    /// parentheses around it, or a `-` in a pattern.
+    fn number_text(&self, span: Span) -> Option<String> {
+        let text = self.source.get(span.start.offset..span.end.offset)?;
+        let mut sign = "";
+        for token in Lexer::new(text.to_string()) {
+            let SpannedToken { token, span } = token.ok()?;
+            match token {
+                Token::LParen => {}
+                Token::Minus => sign = "-",
+                Token::Int(_) | Token::BigInt(_) | Token::Float(_) => {
+                    let number = &text[span.start.offset..span.end.offset];
+                    return Some(format!("{}{}", sign, number));
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            Literal::String(s) => {
+                self.out.push('"');
+                escape(&mut self.out, s);
+                self.out.push('"');
+            }
+            Literal::Int(i) => self.out.push_str(&i.to_string()),
+            Literal::BigInt(i) => self.out.push_str(&i.to_string()),
+            Literal::Float(x) if x.is_infinite() => self.out.push_str("1e999"),
+            // Debug formatting always keeps a `.` or exponent, so the number
+            // lexes back as a float.
+            Literal::Float(x) => self.out.push_str(&format!("{:?}", x)),
+            Literal::Bool(b) => self.out.push_str(&b.to_string()),
+            Literal::Null => self.out.push_str("null"),
+            Literal::Array(items) => self.items(
+                ("[", ']'),
+                items,
+                |item| item.span,
+                |printer, item| printer.spanned_expression(item),
+            ),
+            Literal::Hash(pairs) => self.items(
+                ("{", '}'),
+                pairs,
+                |(key, value)| key.span.to(value.span),
+                |printer, (key, value)| {
+                    printer.spanned_expression(key);
+                    printer.out.push_str(": ");
+                    printer.spanned_expression(value);
+                },
+            ),
+        }
+    }
+
+    /// Writes the items of an array or hash literal on one line if they fit,
+    /// or otherwise one per line with a trailing comma. Comments inside the
+    /// literal also force it onto several lines so they can stay in place.
+    fn items<T>(
+        &mut self,
+        delimiters: (&str, char),
+        items: &[T],
+        span: fn(&T) -> Span,
+        item: fn(&mut Self, &T),
+    ) {
+        if self.flat || items.is_empty() {
+            self.inline_items(delimiters, items, item);
+            return;
+        }
+        if !self.comments_among(items, span) {
+            let mut flat = Printer {
+                flat: true,
+                source: self.source,
+                ..Printer::default()
+            };
+            flat.inline_items(delimiters, items, item);
+            if !flat.out.contains('\n') && self.column() + flat.out.chars().count() <= MAX_WIDTH {
+                self.out.push_str(&flat.out);
+                return;
+            }
+        }
+        self.item_lines(delimiters, items, span, item, true);
+    }
+
+    /// Writes a call's arguments on one line, unless there are comments
+    /// among them, in which case each argument gets a line of its own so the
+    /// comments can stay in place.
+    fn arguments(&mut self, open: &str, args: &[(Option<&Ident>, &Spanned<Expression>)]) {
+        let span = |(_, arg): &(Option<&Ident>, &Spanned<Expression>)| arg.span;
+        let item = |printer: &mut Self, (name, arg): &(Option<&Ident>, &Spanned<Expression>)| {
+            if let Some(name) = name {
+                printer.out.push_str(&name.0);
+                printer.out.push_str(": ");
+            }
+            printer.spanned_expression(arg);
+        };
+        // Calls don't allow a trailing comma.
+        if !self.flat && self.comments_among(args, span) {
+            self.item_lines((open, ')'), args, span, item, false);
+        } else {
+            self.inline_items((open, ')'), args, item);
+        }
+    }
+
+    /// Whether a comment that hasn't been written yet comes before `end` but
+    /// outside `span`.
+    fn comments_around(&self, span: Span, end: usize) -> bool {
+        self.comments
+            .iter()
+            .take_while(|comment| comment.span.start.offset < end)
+            .any(|comment| {
+                comment.span.start.offset < span.start.offset
+                    || comment.span.start.offset >= span.end.offset
+            })
+    }
+
+    /// Whether a comment that hasn't been written yet comes before the end of
+    /// the last of `items`.
+    fn comments_among<T>(&self, items: &[T], span: fn(&T) -> Span) -> bool {
+        match (items.last(), self.comments.first()) {
+            (Some(last), Some(comment)) => comment.span.start.offset < span(last).end.offset,
+            _ => false,
+        }
+    }
+
+    fn inline_items<T>(
+        &mut self,
+        (open, close): (&str, char),
+        items: &[T],
+        item: fn(&mut Self, &T),
+    ) {
+        self.out.push_str(open);
+        for (i, x) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            item(self, x);
+        }
+        self.out.push(close);
+    }
+
+    /// Writes each of `items` on its own line along with the comments around
+    /// it.
+    fn item_lines<T>(
+        &mut self,
+        (open, close): (&str, char),
+        items: &[T],
+        span: fn(&T) -> Span,
+        item: fn(&mut Self, &T),
+        trailing_comma: bool,
+    ) {
+        self.out.push_str(open);
+        self.out.push('\n');
+        self.indent += 1;
+        self.last_line = None;
+        for (i, x) in items.iter().enumerate() {
+            self.comments_before(span(x).start.offset);
+            self.indentation();
+            item(self, x);
+            if trailing_comma || i + 1 < items.len() {
+                self.out.push(',');
+            }
+            self.trailing_comments(span(x).end);
+            self.out.push('\n');
+        }
+        self.indent -= 1;
+        self.indentation();
+        self.out.push(close);
+    }
+}
+
+/// Whether `expr` is written starting with a hash literal, as in
+/// `{"a": 1}["a"]` or `{"a": 1} == x`.
+fn starts_with_hash(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal(Literal::Hash(_)) => true,
+        Expression::Infix(_, left, _)
+        | Expression::Range { start: left, .. }
+        | Expression::Call { function: left, .. }
+        | Expression::Index { left, .. }
+        | Expression::Slice { left, .. } => starts_with_hash(&left.node),
+        _ => false,
+    }
+}
+
+/// Finds the first comment in `source` that `formatted` lost, or that isn't
+/// between the same tokens there. Separators and parentheses, which the
+/// printer adds and drops, are not counted as tokens.
+fn moved_comment(source: &str, formatted: &str) -> Option<Span> {
+    let after = comment_neighbours(formatted);
+    for (i, (comment, neighbours)) in comment_neighbours(source).into_iter().enumerate() {
+        match after.get(i) {
+            Some((_, other)) if same_neighbours(&neighbours, other) => {}
+            _ => return Some(comment.span),
+        }
+    }
+    None
+}
+
+type Neighbours = (Option<Token>, Option<Token>);
+
+/// Pairs each comment in `source`, including those inside `${...}`, with the
+/// tokens before and after it.
+fn comment_neighbours(source: &str) -> Vec<(Comment, Neighbours)> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut comments = vec![];
+    let mut tokens = vec![];
+    for token in lexer.by_ref().flatten() {
+        interpolation_comments(&token.token, &mut comments);
+        match token.token {
+            Token::Comma | Token::SemiColon | Token::LParen | Token::RParen | Token::Eof => {}
+            _ => tokens.push(token),
+        }
+    }
+    comments.append(&mut lexer.comments);
+    comments.sort_by_key(|comment| comment.span.start.offset);
+
+    comments
+        .into_iter()
+        .map(|comment| {
+            let next = tokens.partition_point(|t| t.span.start.offset < comment.span.start.offset);
+            let before = next.checked_sub(1).map(|i| tokens[i].token.clone());
+            let after = tokens.get(next).map(|t| t.token.clone());
+            (comment, (before, after))
+        })
+        .collect()
+}
+
+/// Collects the comments in the code of an interpolated string.
+fn interpolation_comments(token: &Token, comments: &mut Vec<Comment>) {
+    if let Token::Interpolated(parts) = token {
+        for part in parts {
+            if let StringPart::Code(code, start) = part {
+                let mut lexer = Lexer::new_at(code.clone(), *start);
+                for token in lexer.by_ref().flatten() {
+                    interpolation_comments(&token.token, comments);
+                }
+                comments.append(&mut lexer.comments);
+            }
+        }
+    }
+}
+
+/// Compares neighbouring tokens, counting interpolated strings as the same
+/// since the printer reformats the code inside them.
+fn same_neighbours(a: &Neighbours, b: &Neighbours) -> bool {
+    let same = |a: &Option<Token>, b: &Option<Token>| match (a, b) {
+        (Some(Token::Interpolated(_)), Some(Token::Interpolated(_))) => true,
+        _ => a == b,
+    };
+    same(&a.0, &b.0) && same(&a.1, &b.1)
+}
+
+fn escape(out: &mut String, s: &str) {
+    let mut chars = s.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            ch if ch.is_control() => out.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Spanned<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.literal(self);
+        f.write_str(&printer.out)
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.expression(self);
+        f.write_str(&printer.out)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.pattern(self);
+        f.write_str(&printer.out)
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.statement(self);
+        f.write_str(&printer.out)
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut printer = Printer::default();
+        printer.program(self);
+        f.write_str(&printer.out)
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    lexer::Lexer,
+    parser::Parser,
+    printer::{error::FormatError, format, reparse},
+};
+
+#[test]
+fn test_format() {
+    let tests = vec![
+        ("let   x=1+2*3;", "let x = 1 + 2 * 3;\n"),
+        ("let f = fn (a,b) { a-b };", "let f = fn(a, b) {\n    a - b\n};\n"),
+        (
+            "fn add (a, b=1) { a+b };",
+            "fn add(a, b = 1) {\n    a + b\n}\n",
+        ),
+        (
+            "xs |> filter(fn(x) { x > 0 }) |> map(fn(x) { x * 2 }) |> len",
+            "xs\n    |> filter(fn(x) {\n        x > 0\n    })\n    |> map(fn(x) {\n        x * 2\n    })\n    |> len;\n",
+        ),
+        ("xs|>map(f)", "xs |> map(f);\n"),
+        ("update x+=1", "update x += 1;\n"),
+        (
+            "[0xFF, 0b1010_0101, 1_000_000, 1.5e3, (0o17)]",
+            "[0xFF, 0b1010_0101, 1_000_000, 1.5e3, 0o17];\n",
+        ),
+        (
+            "match (x) { -0x1 => 1, {1_0: y} => y }",
+            "match (x) {\n    -0x1 => 1,\n    {1_0: y} => y,\n};\n",
+        ),
+        ("\"${0xFF+1}\"", "\"${0xFF + 1}\";\n"),
+        ("(1 + 2) * 3; 1 + (2 * 3);", "(1 + 2) * 3;\n1 + 2 * 3;\n"),
+        ("let x = 1;\n\n\n\nlet y = 2;", "let x = 1;\n\nlet y = 2;\n"),
+        (
+            "if (x) { 1 } else if (y) { 2 } else { 3 }",
+            "if (x) {\n    1\n} else if (y) {\n    2\n} else {\n    3\n};\n",
+        ),
+        (
+            "while (i < 3) { update i += 1; }",
+            "while (i < 3) {\n    update i += 1;\n}\n",
+        ),
+        (r#"{"a": [1,2,],}"#, "{\"a\": [1, 2]};\n"),
+        (
+            r#"{"a": fn() { 1 }}"#,
+            "{\n    \"a\": fn() {\n        1\n    },\n};\n",
+        ),
+        (
+            "let xs = [100000000000, 200000000000, 300000000000, 400000000000, 500000000000, 600000000000, 700000000000];",
+            "let xs = [\n    100000000000,\n    200000000000,\n    300000000000,\n    400000000000,\n    500000000000,\n    600000000000,\n    700000000000,\n];\n",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(format(input).unwrap(), expected);
+        assert_eq!(format(expected).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_format_comments() {
+    let input = r#"# header
+
+let x = 1; # trailing
+/* block
+   comment */
+let f = fn() {
+    # leading
+    let y = 2;
+
+
+    y # value
+    # closing
+}; # after
+let xs = [
+    1, # one
+    # two
+    2,
+];
+while (true) { break; } # loop
+f(1, # one
+  # two
+  x: 2 # three
+)
+match (x) {
+    0 => { # zero
+        0 }
+    _ => {
+        1 # one
+    }
+}
+# footer
+"#;
+    let expected = r#"# header
+
+let x = 1; # trailing
+/* block
+   comment */
+let f = fn() {
+    # leading
+    let y = 2;
+
+    y # value
+    # closing
+}; # after
+let xs = [
+    1, # one
+    # two
+    2,
+];
+while (true) {
+    break;
+} # loop
+f(
+    1, # one
+    # two
+    x: 2 # three
+);
+match (x) {
+    0 => {
+        # zero
+        0
+    }
+    _ => {
+        1 # one
+    }
+};
+# footer
+"#;
+
+    assert_eq!(format(input).unwrap(), expected);
+    assert_eq!(format(expected).unwrap(), expected);
+}
+
+#[test]
+fn test_format_is_stable() {
+    let files = vec![
+        include_str!("../../libraries/std.hera"),
+        include_str!("../../examples/factorial.hera"),
+        include_str!("../../examples/fibonacci.hera"),
+        include_str!("../../examples/fizzbuzz.hera"),
+        include_str!("../../examples/helloworld.hera"),
+        include_str!("../../examples/loop.hera"),
+    ];
+
+    for file in files {
+        assert_eq!(format(file).unwrap(), file);
+    }
+}
+
+#[test]
+fn test_format_errors() {
+    let errors = format("let x = ;\nlet = 1;").unwrap_err();
+    let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Expected an expression, got `;` at line 1, column 9",
+            "Expected an identifier, got `=` at line 2, column 5",
+        ]
+    );
+}
+
+#[test]
+fn test_format_moved_comments() {
+    let tests = vec![
+        ("let f = fn(a, # first\n b) { a };", "line 1, column 15"),
+        ("let s = \"${x # note\n}\";", "line 1, column 14"),
+        (
+            "if (x) { 1 } else {\n    # c\n    if (y) { 2 }\n}",
+            "line 2, column 5",
+        ),
+        ("let x = 1 + /* c */ 2;", "line 1, column 13"),
+    ];
+
+    for (input, location) in tests {
+        let errors: Vec<String> = format(input)
+            .unwrap_err()
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![format!(
+                "Cannot format the comment at {} in place",
+                location
+            )]
+        );
+    }
+}
+
+#[test]
+fn test_format_checks_the_output() {
+    let program = Parser::new(Lexer::new(String::from("x + 1;"))).parse_program();
+    assert_eq!(
+        reparse(&program, String::from("x + 1;\n")),
+        Ok(String::from("x + 1;\n"))
+    );
+    for output in ["x - 1;\n", "x +;\n"] {
+        let errors = reparse(&program, String::from(output)).unwrap_err();
+        assert_eq!(errors, vec![FormatError::ChangedProgram]);
+        assert_eq!(
+            errors[0].to_string(),
+            "Formatting would change the meaning of the code"
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     eval::{env::Env, object::Object, Eval},
     lexer::Lexer,
-    parser::Parser,
+    parser::{error::ParseError, Parser},
 };
 use std::{
     cell::RefCell,
@@ -37,7 +37,7 @@ pub fn start() {
     }
 }
 
-fn print_parse_errors(errors: Vec<String>) {
+fn print_parse_errors(errors: Vec<ParseError>) {
     for e in errors.iter() {
         println!("\t{}", e);
     }
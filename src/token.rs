@@ -1,22 +1,39 @@
+use num_bigint::BigInt;
 use std::fmt::{self, Debug, Formatter};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
+    /// Stands in for input the lexer rejected, after its error is reported.
     Illegal,
     Eof,
 
     Ident(String),
-    Int(i32),
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
     Str(String),
+    Interpolated(Vec<StringPart>),
     Bool(bool),
 
     Assign,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+    PercentAssign,
     Plus,
     Minus,
     Bang,
     Asterisk,
     Slash,
     Percent,
+    Power,
+    Ampersand,
+    Bar,
+    Caret,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
 
     Lt,
     Gt,
@@ -24,10 +41,18 @@ pub enum Token {
     GtEq,
     Equal,
     NotEq,
+    And,
+    Or,
+    Pipe,
+    Coalesce,
 
     Comma,
     SemiColon,
     Colon,
+    FatArrow,
+    Ellipsis,
+    DotDot,
+    DotDotEq,
 
     RParen,
     LParen,
@@ -35,6 +60,11 @@ pub enum Token {
     RBrace,
     LBracket,
     RBracket,
+    /// `?[`, opening an index that gives null on a null value.
+    OptionalLBracket,
+    /// `?.`, which comes before the arguments of a call that gives null on a
+    /// null function.
+    OptionalDot,
 
     Function,
     Let,
@@ -43,10 +73,165 @@ pub enum Token {
     Else,
     Return,
     Import,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Match,
+    Null,
 }
 
+/// A piece of an interpolated string literal: either plain text or the
+/// source of an embedded `${...}` expression along with where it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart {
+    Literal(String),
+    Code(String, Position),
+}
+
+/// Writes tokens the way they appear in source code.
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self)
+        let text = match self {
+            Token::Illegal => "illegal",
+            Token::Eof => "",
+            Token::Ident(ident) => ident,
+            Token::Int(i) => return write!(f, "{}", i),
+            Token::BigInt(i) => return write!(f, "{}", i),
+            Token::Float(float) => return write!(f, "{:?}", float),
+            Token::Str(s) => return write!(f, "{:?}", s),
+            Token::Interpolated(parts) => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match part {
+                        StringPart::Literal(s) => write!(f, "{}", s.escape_debug())?,
+                        StringPart::Code(code, _) => write!(f, "${{{}}}", code)?,
+                    }
+                }
+                return write!(f, "\"");
+            }
+            Token::Bool(b) => return write!(f, "{}", b),
+            Token::Assign => "=",
+            Token::PlusAssign => "+=",
+            Token::MinusAssign => "-=",
+            Token::AsteriskAssign => "*=",
+            Token::SlashAssign => "/=",
+            Token::PercentAssign => "%=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Bang => "!",
+            Token::Asterisk => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::Power => "**",
+            Token::Ampersand => "&",
+            Token::Bar => "|",
+            Token::Caret => "^",
+            Token::Tilde => "~",
+            Token::ShiftLeft => "<<",
+            Token::ShiftRight => ">>",
+            Token::Lt => "<",
+            Token::Gt => ">",
+            Token::LtEq => "<=",
+            Token::GtEq => ">=",
+            Token::Equal => "==",
+            Token::NotEq => "!=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Pipe => "|>",
+            Token::Coalesce => "??",
+            Token::Comma => ",",
+            Token::SemiColon => ";",
+            Token::Colon => ":",
+            Token::FatArrow => "=>",
+            Token::Ellipsis => "...",
+            Token::DotDot => "..",
+            Token::DotDotEq => "..=",
+            Token::RParen => ")",
+            Token::LParen => "(",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::OptionalLBracket => "?[",
+            Token::OptionalDot => "?.",
+            Token::Function => "fn",
+            Token::Let => "let",
+            Token::Update => "update",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::Return => "return",
+            Token::Import => "import",
+            Token::While => "while",
+            Token::For => "for",
+            Token::In => "in",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Match => "match",
+            Token::Null => "null",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// A location in the source: a byte offset plus the 1-based line and column
+/// (counted in characters) it corresponds to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, column: usize) -> Self {
+        Position {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+/// The region of source a token or AST node was read from. `end` points just
+/// past the last character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
     }
+
+    /// Returns a span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.start.line, self.start.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+/// A comment skipped over by the lexer, including its `#` or `/* */`
+/// delimiters, kept so the formatter can write it back out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
 }